@@ -0,0 +1,19 @@
+/// Open `url` in the default browser. macOS: `open`, Linux: `xdg-open`,
+/// Windows: `start` via cmd. Failure is ignored — there's no useful UI
+/// for "couldn't open the link", and the user can read the URL on screen.
+pub fn open(url: &str) {
+    #[cfg(target_os = "macos")]
+    let opener = ("open", None::<&str>);
+    #[cfg(target_os = "linux")]
+    let opener = ("xdg-open", None::<&str>);
+    #[cfg(target_os = "windows")]
+    let opener = ("cmd", Some("/C start"));
+    #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+    let opener = ("xdg-open", None::<&str>); // best-guess fallback
+
+    let mut cmd = std::process::Command::new(opener.0);
+    if let Some(arg) = opener.1 {
+        cmd.arg(arg);
+    }
+    let _ = cmd.arg(url).spawn();
+}
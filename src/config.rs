@@ -0,0 +1,252 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::patterns::Rule;
+use crate::probe::Probe;
+use crate::writer::validate_unit_name;
+
+/// Config files `sutra up` looks for, in order of preference.
+pub const CONFIG_FILES: [&str; 2] = ["sutra.toml", "Procfile"];
+
+/// A project's units, as declared in `sutra.toml` or a `Procfile`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Directory containing the config file; the environment's `DIR`.
+    pub dir: PathBuf,
+    /// Units in declaration order.
+    pub units: Vec<UnitConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitConfig {
+    pub name: String,
+    /// Shell command, run with `sh -c`.
+    pub command: String,
+    /// Working directory; defaults to [`Config::dir`].
+    pub dir: PathBuf,
+    pub port: Option<u16>,
+    /// Promotes the unit from `running` to `ready` once it passes.
+    pub ready: Option<Probe>,
+    /// Output patterns that set the unit's state, first match wins.
+    pub rules: Vec<Rule>,
+}
+
+/// One `[units.<name>]` table in `sutra.toml`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawUnit {
+    command: String,
+    dir: Option<PathBuf>,
+    port: Option<u16>,
+    ready: Option<String>,
+    /// `state = "regex"` pairs, in match order.
+    patterns: Option<toml::Table>,
+}
+
+/// Find the config file for `dir`: `sutra.toml` if present, else `Procfile`.
+pub fn discover(dir: &Path) -> Option<PathBuf> {
+    CONFIG_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Load `path`, picking the format from its filename (`*.toml` is a sutra
+/// config, anything else is read as a Procfile).
+pub fn load(path: &Path) -> io::Result<Config> {
+    let contents = fs::read_to_string(path)?;
+    let dir = fs::canonicalize(path)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("/"));
+    let units = if path.extension().is_some_and(|ext| ext == "toml") {
+        parse_toml(&contents, &dir)
+    } else {
+        parse_procfile(&contents, &dir)
+    }
+    .map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {e}", path.display()),
+        )
+    })?;
+    if units.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: no units declared", path.display()),
+        ));
+    }
+    Ok(Config { dir, units })
+}
+
+/// Parse `sutra.toml`:
+///
+/// ```toml
+/// [units.server]
+/// command = "cargo run"
+/// port = 3000
+/// ready = "http://127.0.0.1:3000/health"   # or "tcp", "tcp:3000"
+///
+/// [units.vite]
+/// command = "npm run dev"
+/// dir = "web"
+/// port = 5173
+/// ready = "tcp"
+///
+/// [units.vite.patterns]                    # first match wins
+/// ready = "Local:.*http://"
+/// failed = "error"
+/// ```
+fn parse_toml(contents: &str, dir: &Path) -> Result<Vec<UnitConfig>, String> {
+    let doc: toml::Table = contents
+        .parse()
+        .map_err(|e: toml::de::Error| e.to_string())?;
+    let mut units = Vec::new();
+    for (key, value) in &doc {
+        if key != "units" {
+            return Err(format!("unknown top-level key {key:?}"));
+        }
+        let table = value
+            .as_table()
+            .ok_or_else(|| "`units` must be a table of [units.<name>] sections".to_string())?;
+        // `toml` is built with `preserve_order`, so units start in the
+        // order they're written.
+        for (name, raw) in table {
+            let raw: RawUnit = raw
+                .clone()
+                .try_into()
+                .map_err(|e: toml::de::Error| format!("units.{name}: {}", e.message()))?;
+            let ready = raw
+                .ready
+                .as_deref()
+                .map(|spec| Probe::parse(spec, raw.port))
+                .transpose()
+                .map_err(|e| format!("units.{name}.ready: {e}"))?;
+            let mut rules = Vec::new();
+            for (state, pattern) in raw.patterns.iter().flatten() {
+                let pattern = pattern
+                    .as_str()
+                    .ok_or_else(|| format!("units.{name}.patterns.{state}: expected a string"))?;
+                rules.push(
+                    Rule::new(state, pattern)
+                        .map_err(|e| format!("units.{name}.patterns.{state}: {e}"))?,
+                );
+            }
+            let mut unit = unit(name, raw.command, raw.dir, raw.port, ready, dir)?;
+            unit.rules = rules;
+            units.push(unit);
+        }
+    }
+    Ok(units)
+}
+
+/// Parse a Procfile: `<name>: <command>` per line, `#` comments.
+fn parse_procfile(contents: &str, dir: &Path) -> Result<Vec<UnitConfig>, String> {
+    let mut units = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, command) = line
+            .split_once(':')
+            .ok_or_else(|| format!("line {}: expected `<name>: <command>`", i + 1))?;
+        units.push(unit(
+            name.trim(),
+            command.trim().to_string(),
+            None,
+            None,
+            None,
+            dir,
+        )?);
+    }
+    Ok(units)
+}
+
+fn unit(
+    name: &str,
+    command: String,
+    unit_dir: Option<PathBuf>,
+    port: Option<u16>,
+    ready: Option<Probe>,
+    dir: &Path,
+) -> Result<UnitConfig, String> {
+    validate_unit_name(name).map_err(|e| e.to_string())?;
+    if command.trim().is_empty() {
+        return Err(format!("unit {name:?} has an empty command"));
+    }
+    Ok(UnitConfig {
+        name: name.to_string(),
+        command,
+        dir: unit_dir.map_or_else(|| dir.to_path_buf(), |d| dir.join(d)),
+        port,
+        ready,
+        rules: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_toml_in_declaration_order() {
+        let units = parse_toml(
+            r#"
+            [units.vite]
+            command = "npm run dev"
+            dir = "web"
+            port = 5173
+            ready = "tcp"
+
+            [units.vite.patterns]
+            ready = "Local:"
+            building = "hmr update"
+
+            [units.server]
+            command = "cargo run"
+            "#,
+            Path::new("/code/app"),
+        )
+        .unwrap();
+        assert_eq!(units.len(), 2);
+        assert_eq!(units[0].name, "vite");
+        assert_eq!(units[0].dir, PathBuf::from("/code/app/web"));
+        assert_eq!(units[0].ready, Some(Probe::Tcp(5173)));
+        let rules: Vec<_> = units[0].rules.iter().map(|r| r.to_string()).collect();
+        assert_eq!(rules, ["ready=Local:", "building=hmr update"]);
+        assert_eq!(units[1].name, "server");
+        assert_eq!(units[1].port, None);
+    }
+
+    #[test]
+    fn rejects_bad_toml_units() {
+        let dir = Path::new("/code/app");
+        assert!(parse_toml("[units.a]\ncommand = \"x\"\nprot = 1\n", dir).is_err());
+        assert!(parse_toml("[units.\"a.b\"]\ncommand = \"x\"\n", dir).is_err());
+        assert!(parse_toml("[units.a]\ncommand = \"x\"\nready = \"tcp\"\n", dir).is_err());
+        assert!(
+            parse_toml(
+                "[units.a]\ncommand = \"x\"\npatterns = { up = \"x\" }\n",
+                dir
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_procfile() {
+        let units = parse_procfile(
+            "# dev processes\nweb: bundle exec rails s -p 3000\n\nworker:  sidekiq\n",
+            Path::new("/code/app"),
+        )
+        .unwrap();
+        let names: Vec<_> = units.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, ["web", "worker"]);
+        assert_eq!(units[0].command, "bundle exec rails s -p 3000");
+        assert!(parse_procfile("just a command\n", Path::new("/")).is_err());
+    }
+}
@@ -1,11 +1,18 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
 use iced::widget::{
-    Column, column, container, mouse_area, row, scrollable, svg, text, text_editor, tooltip,
+    Column, column, container, mouse_area, progress_bar, row, scrollable, svg, text, text_editor,
+    tooltip,
 };
 use iced::{Element, Font, Subscription, Theme, color};
 
-use crate::model::{self, Environment, State};
+use crate::journal::Journal;
+use crate::model::{self, Environment, SinceTracker, State};
 use crate::notifications::Notifier;
+use crate::probe::{HealthProber, PortProber};
 use crate::watcher::{RegistryWatcher, WatchEvent};
+use crate::writer::{self, GcReport};
 
 /// Set the macOS dock icon from embedded PNG bytes.
 #[cfg(target_os = "macos")]
@@ -45,6 +52,8 @@ const ICON_BELL_OFF: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg" width="
 
 const ICON_SQUARE: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="24" viewBox="0 0 24 24" fill="currentColor" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round"><rect x="3" y="3" width="18" height="18" rx="2"/></svg>"#;
 
+const ICON_TRASH: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="24" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round"><polyline points="3 6 5 6 21 6"/><path d="M19 6v14a2 2 0 0 1-2 2H7a2 2 0 0 1-2-2V6m3 0V4a2 2 0 0 1 2-2h4a2 2 0 0 1 2 2v2"/><line x1="10" y1="11" x2="10" y2="17"/><line x1="14" y1="11" x2="14" y2="17"/></svg>"#;
+
 const ICON_HELP_CIRCLE: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="24" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round"><circle cx="12" cy="12" r="10"/><path d="M9.09 9a3 3 0 0 1 5.83 1c0 2-3 3-3 3"/><line x1="12" y1="17" x2="12.01" y2="17"/></svg>"#;
 
 const ICON_COPY: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="24" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round"><rect x="9" y="9" width="13" height="13" rx="2" ry="2"/><path d="M5 15H4a2 2 0 0 1-2-2V4a2 2 0 0 1 2-2h9a2 2 0 0 1 2 2v1"/></svg>"#;
@@ -164,24 +173,39 @@ enum Message {
     Tick,
     WatchEvent,
     ToggleGlobalMute,
-    ToggleUnitMute { env_id: String, unit_name: String },
+    ToggleUnitMute {
+        env_id: String,
+        unit_name: String,
+    },
     ToggleGlobalNotifications,
-    ToggleUnitNotifications { env_id: String, unit_name: String },
+    ToggleUnitNotifications {
+        env_id: String,
+        unit_name: String,
+    },
     ToggleTheme,
     ToggleHelp,
     OpenHelp,
     CloseHelp,
     CopyToClipboard(String),
-    OpenBrowser { port: u16 },
     OpenUrl(String),
     PromptAction(text_editor::Action),
-    TerminateEnv { pid: u32 },
-    HoverUnit { env_id: String, unit_name: String },
+    TerminateEnv {
+        pid: u32,
+    },
+    /// List what cleaning up would remove and ask for confirmation.
+    CleanUpDead,
+    ConfirmCleanUp,
+    CancelCleanUp,
+    HoverUnit {
+        env_id: String,
+        unit_name: String,
+    },
     UnhoverUnit,
     Quit,
 }
 
 struct App {
+    state_dir: PathBuf,
     envs: Vec<Environment>,
     notifier: Notifier,
     dark_mode: bool,
@@ -195,9 +219,25 @@ struct App {
     /// We accept all non-Edit actions so the user can select and scroll
     /// the prompt text, but block Edit actions to keep it read-only.
     prompt_content: text_editor::Content,
+    /// Keeps each unit's "since" steady across status rewrites.
+    since: SinceTracker,
+    /// Checks whether each declared port is accepting connections.
+    ports: PortProber,
+    /// Runs the `<UNIT>_HEALTH` checks.
+    health: HealthProber,
+    /// What the clean-up button would remove, awaiting confirmation.
+    pending_cleanup: Option<GcReport>,
+    /// Outcome of the last clean-up, shown under the toolbar until
+    /// [`NOTICE_DURATION`] has passed.
+    notice: Option<(String, Instant)>,
 }
 
-pub fn run() {
+/// How long a notice stays under the toolbar.
+const NOTICE_DURATION: Duration = Duration::from_secs(5);
+
+/// Entry point for the GUI. Called from main with the resolved registry
+/// directory.
+pub fn run(state_dir: PathBuf) {
     #[cfg(target_os = "macos")]
     set_dock_icon();
 
@@ -211,12 +251,19 @@ pub fn run() {
             icon,
             ..Default::default()
         })
-        .run_with(|| {
-            let envs = model::load_all();
-            let mut notifier = Notifier::new();
+        .run_with(move || {
+            let mut envs = model::load_all(&state_dir);
+            let mut health = HealthProber::spawn();
+            health.apply(&mut envs);
+            let mut since = SinceTracker::new();
+            since.apply(&mut envs);
+            let mut ports = PortProber::spawn();
+            ports.apply(&mut envs);
+            let mut notifier = Notifier::new().with_journal(Journal::for_state_dir(&state_dir));
             notifier.process(&envs);
             (
                 App {
+                    state_dir,
                     envs,
                     notifier,
                     dark_mode: false,
@@ -224,6 +271,11 @@ pub fn run() {
                     show_help: false,
                     copied_flash: false,
                     prompt_content: text_editor::Content::with_text(HELP_AGENT_PROMPT.trim_end()),
+                    since,
+                    ports,
+                    health,
+                    pending_cleanup: None,
+                    notice: None,
                 },
                 iced::Task::none(),
             )
@@ -231,19 +283,33 @@ pub fn run() {
         .expect("failed to launch GUI");
 }
 
+/// Re-read the registry and fire notifications for any transitions.
+fn reload(app: &mut App) {
+    app.envs = model::load_all(&app.state_dir);
+    app.health.apply(&mut app.envs);
+    app.since.apply(&mut app.envs);
+    app.ports.apply(&mut app.envs);
+    app.notifier.process(&app.envs);
+}
+
 fn update(app: &mut App, message: Message) -> iced::Task<Message> {
     match message {
         Message::Tick => {
-            app.envs = model::load_all();
-            app.notifier.process(&app.envs);
+            reload(app);
             // Clear the copy-flash on the next periodic refresh. WatchEvent
             // doesn't clear it, so unrelated filesystem activity won't snap
             // the "Copied!" label away early.
             app.copied_flash = false;
+            if app
+                .notice
+                .as_ref()
+                .is_some_and(|(_, shown)| shown.elapsed() >= NOTICE_DURATION)
+            {
+                app.notice = None;
+            }
         }
         Message::WatchEvent => {
-            app.envs = model::load_all();
-            app.notifier.process(&app.envs);
+            reload(app);
         }
         Message::ToggleGlobalMute => {
             app.notifier.toggle_global_mute();
@@ -285,33 +351,15 @@ fn update(app: &mut App, message: Message) -> iced::Task<Message> {
         Message::ToggleUnitNotifications { env_id, unit_name } => {
             app.notifier.toggle_unit_notifications(&env_id, &unit_name);
         }
-        Message::OpenBrowser { port } => {
-            let _ = std::process::Command::new("open")
-                .arg(format!("http://localhost:{port}"))
-                .spawn();
-        }
-        Message::OpenUrl(url) => {
-            // Cross-platform browser launcher. macOS: `open`, Linux:
-            // `xdg-open`, Windows: `start` via cmd. We probe one and
-            // ignore failure — there's no useful UI for "couldn't open
-            // the link", and the user can read the URL on screen.
-            #[cfg(target_os = "macos")]
-            let opener = ("open", None::<&str>);
-            #[cfg(target_os = "linux")]
-            let opener = ("xdg-open", None::<&str>);
-            #[cfg(target_os = "windows")]
-            let opener = ("cmd", Some("/C start"));
-            #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
-            let opener = ("xdg-open", None::<&str>); // best-guess fallback
-
-            let mut cmd = std::process::Command::new(opener.0);
-            if let Some(arg) = opener.1 {
-                cmd.arg(arg);
-            }
-            let _ = cmd.arg(url).spawn();
-        }
+        Message::OpenUrl(url) => crate::browser::open(&url),
         Message::TerminateEnv { pid } => {
-            if let Ok(raw_pid) = i32::try_from(pid) {
+            // Re-check first: a PID reused since the last refresh must
+            // never be signaled.
+            let still_alive = app
+                .envs
+                .iter()
+                .any(|env| env.pid == pid && env.check_alive());
+            if let Some(raw_pid) = i32::try_from(pid).ok().filter(|_| still_alive) {
                 // Sutra is a situational-awareness dashboard with
                 // intentionally limited control: one shutdown button
                 // per env, which sends a single SIGTERM to the one
@@ -327,6 +375,27 @@ fn update(app: &mut App, message: Message) -> iced::Task<Message> {
                 );
             }
         }
+        Message::CleanUpDead => match writer::gc(&app.state_dir, writer::ORPHAN_MIN_AGE, true) {
+            Ok(report) if report.is_empty() => {
+                app.notice = Some(("Nothing to clean up".to_string(), Instant::now()));
+            }
+            Ok(report) => app.pending_cleanup = Some(report),
+            Err(e) => {
+                app.notice = Some((format!("Clean up failed: {e}"), Instant::now()));
+            }
+        },
+        Message::ConfirmCleanUp => {
+            app.pending_cleanup = None;
+            let outcome = match writer::gc(&app.state_dir, writer::ORPHAN_MIN_AGE, false) {
+                Ok(report) => format!("Removed {}", report.summary()),
+                Err(e) => format!("Clean up failed: {e}"),
+            };
+            app.notice = Some((outcome, Instant::now()));
+            reload(app);
+        }
+        Message::CancelCleanUp => {
+            app.pending_cleanup = None;
+        }
         Message::HoverUnit { env_id, unit_name } => {
             app.hovered_unit = Some((env_id, unit_name));
         }
@@ -385,45 +454,70 @@ fn view(app: &App) -> Element<'_, Message> {
             "Integration help"
         };
 
-        let toolbar_row = row![
-            iced::widget::horizontal_space(),
-            tooltip(
-                mouse_area(icon_svg(ICON_HELP_CIRCLE, 16.0, help_color))
-                    .on_press(Message::ToggleHelp),
-                tip_bubble(help_tip, &pal),
-                tooltip::Position::Bottom,
-            )
-            .gap(4),
-            text("\u{00b7}").size(8).color(pal.muted),
-            tooltip(
-                mouse_area(icon_svg(mute_icon, 16.0, icon_color))
-                    .on_press(Message::ToggleGlobalMute),
-                tip_bubble(mute_tip, &pal),
-                tooltip::Position::Bottom,
-            )
-            .gap(4),
-            text("\u{00b7}").size(8).color(pal.muted), // middle dot separator
-            tooltip(
-                mouse_area(icon_svg(notif_icon, 16.0, icon_color))
-                    .on_press(Message::ToggleGlobalNotifications),
-                tip_bubble(notif_tip, &pal),
-                tooltip::Position::Bottom,
-            )
-            .gap(4),
-            text("\u{00b7}").size(8).color(pal.muted),
-            tooltip(
-                mouse_area(icon_svg(theme_icon, 16.0, icon_color)).on_press(Message::ToggleTheme),
-                tip_bubble(theme_tip, &pal),
-                tooltip::Position::Bottom,
-            )
-            .gap(4),
-        ]
-        .spacing(8)
-        .align_y(iced::Alignment::Center);
+        let mut toolbar_row = row![iced::widget::horizontal_space()]
+            .spacing(8)
+            .align_y(iced::Alignment::Center);
+
+        // Only offered while there's something to clean up.
+        if app.envs.iter().any(|e| !e.alive) {
+            toolbar_row = toolbar_row
+                .push(
+                    tooltip(
+                        mouse_area(icon_svg(ICON_TRASH, 16.0, icon_color))
+                            .on_press(Message::CleanUpDead),
+                        tip_bubble("Clean up dead environments", &pal),
+                        tooltip::Position::Bottom,
+                    )
+                    .gap(4),
+                )
+                .push(text("\u{00b7}").size(8).color(pal.muted));
+        }
 
-        container(toolbar_row)
+        let toolbar_row = toolbar_row.push(
+            row![
+                tooltip(
+                    mouse_area(icon_svg(ICON_HELP_CIRCLE, 16.0, help_color))
+                        .on_press(Message::ToggleHelp),
+                    tip_bubble(help_tip, &pal),
+                    tooltip::Position::Bottom,
+                )
+                .gap(4),
+                text("\u{00b7}").size(8).color(pal.muted),
+                tooltip(
+                    mouse_area(icon_svg(mute_icon, 16.0, icon_color))
+                        .on_press(Message::ToggleGlobalMute),
+                    tip_bubble(mute_tip, &pal),
+                    tooltip::Position::Bottom,
+                )
+                .gap(4),
+                text("\u{00b7}").size(8).color(pal.muted), // middle dot separator
+                tooltip(
+                    mouse_area(icon_svg(notif_icon, 16.0, icon_color))
+                        .on_press(Message::ToggleGlobalNotifications),
+                    tip_bubble(notif_tip, &pal),
+                    tooltip::Position::Bottom,
+                )
+                .gap(4),
+                text("\u{00b7}").size(8).color(pal.muted),
+                tooltip(
+                    mouse_area(icon_svg(theme_icon, 16.0, icon_color))
+                        .on_press(Message::ToggleTheme),
+                    tip_bubble(theme_tip, &pal),
+                    tooltip::Position::Bottom,
+                )
+                .gap(4),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+        );
+
+        let toolbar = container(toolbar_row)
             .padding(iced::Padding::from([6.0, 16.0]))
-            .width(iced::Fill)
+            .width(iced::Fill);
+        match cleanup_bar(app, &pal) {
+            Some(bar) => Element::from(column![toolbar, bar]),
+            None => toolbar.into(),
+        }
     };
 
     if app.show_help {
@@ -435,9 +529,7 @@ fn view(app: &App) -> Element<'_, Message> {
     }
 
     if app.envs.is_empty() {
-        let dir_label = model::state_dir()
-            .map(|p| p.display().to_string())
-            .unwrap_or_else(|| "~/.dev-runner/".into());
+        let dir_label = app.state_dir.display().to_string();
         let empty_msg = column![
             text("No environments found.").size(14).color(pal.muted),
             text(format!("Watching {} for environments", dir_label))
@@ -480,6 +572,33 @@ fn view(app: &App) -> Element<'_, Message> {
     .into()
 }
 
+/// Confirmation for a pending clean-up, or the outcome of the last one.
+fn cleanup_bar<'a>(app: &App, pal: &Palette) -> Option<Element<'a, Message>> {
+    let content: Element<'a, Message> = match &app.pending_cleanup {
+        Some(report) => row![
+            text(format!("Remove {}?", report.summary()))
+                .size(12)
+                .color(pal.fg),
+            iced::widget::horizontal_space(),
+            mouse_area(text("Remove").size(12).color(pal.red)).on_press(Message::ConfirmCleanUp),
+            mouse_area(text("Cancel").size(12).color(pal.muted)).on_press(Message::CancelCleanUp),
+        ]
+        .spacing(12)
+        .align_y(iced::Alignment::Center)
+        .into(),
+        None => {
+            let (notice, _) = app.notice.as_ref()?;
+            text(notice.clone()).size(12).color(pal.muted).into()
+        }
+    };
+    Some(
+        container(content)
+            .padding(iced::Padding::from([4.0, 16.0]))
+            .width(iced::Fill)
+            .into(),
+    )
+}
+
 /// Help panel — explains the `~/.dev-runner/` contract that any dev
 /// script needs to satisfy to show up in sutra, and offers a copyable
 /// agent prompt that points at the full integration guide on GitHub.
@@ -680,21 +799,42 @@ fn env_card(
     pal: &Palette,
     hovered_unit: &Option<(String, String)>,
 ) -> Element<'static, Message> {
-    let alive_color = if env.alive { pal.green } else { pal.gray };
+    let alive_color = if env.alive {
+        pal.green
+    } else if env.stale_pid {
+        pal.yellow
+    } else {
+        pal.gray
+    };
 
-    // Header: alive dot + name + elapsed + terminate button
+    // Header: alive dot + icon + name + elapsed + terminate button
+    let title = match &env.icon {
+        Some(icon) => format!("{icon} {}", env.display_name()),
+        None => env.display_name().to_string(),
+    };
     let mut header = row![
         text("\u{25cf}").size(10).color(alive_color),
-        text(env.display_name().to_string())
-            .size(15)
-            .color(pal.fg)
-            .font(Font::DEFAULT),
+        text(title).size(15).color(pal.fg).font(Font::DEFAULT),
         iced::widget::horizontal_space(),
         text(env.elapsed_string()).size(12).color(pal.muted),
     ]
     .spacing(6)
     .align_y(iced::Alignment::Center);
 
+    if env.stale_pid {
+        header = header.push(
+            tooltip(
+                text("stale PID").size(11).color(pal.yellow),
+                tip_bubble(
+                    format!("PID {} now belongs to an unrelated process", env.pid),
+                    pal,
+                ),
+                tooltip::Position::Top,
+            )
+            .gap(4),
+        );
+    }
+
     if env.alive {
         let stop_btn: Element<'static, Message> = tooltip(
             mouse_area(icon_svg(ICON_SQUARE, 10.0, pal.red))
@@ -709,6 +849,10 @@ fn env_card(
 
     let mut card_col = column![header].spacing(8);
 
+    if let Some(description) = &env.description {
+        card_col = card_col.push(text(description.clone()).size(12).color(pal.muted));
+    }
+
     if !env.units.is_empty() {
         // Compute fixed pixel widths for table-column alignment.
         // ~7.2px per char at size 12 monospace is a reasonable approximation.
@@ -718,8 +862,9 @@ fn env_card(
         let name_col_w = (max_name_chars as f32 * CHAR_W).ceil() + 4.0;
 
         let has_any_port = env.units.iter().any(|u| env.port_for(&u.name).is_some());
+        // ":65535" plus the listening marker
         let port_col_w: f32 = if has_any_port {
-            6.0 * CHAR_W + 4.0
+            7.0 * CHAR_W + 4.0
         } else {
             0.0
         };
@@ -727,11 +872,20 @@ fn env_card(
         let max_state_chars = env
             .units
             .iter()
-            .map(|u| u.state.to_string().len())
+            .map(|u| u.observed_state().to_string().len())
             .max()
             .unwrap_or(0);
         let state_col_w = (max_state_chars as f32 * CHAR_W).ceil() + 4.0;
 
+        let max_since_chars = env
+            .units
+            .iter()
+            .filter_map(|u| u.since_string())
+            .map(|s| s.len())
+            .max()
+            .unwrap_or(0);
+        let since_col_w = (max_since_chars as f32 * CHAR_W).ceil() + 4.0;
+
         let muted_color = pal.muted;
         let cyan = pal.cyan;
         let hover_bg = pal.hover_bg;
@@ -741,8 +895,8 @@ fn env_card(
         for unit in &env.units {
             let is_muted = notifier.is_unit_muted(&env.id, &unit.name);
             let is_notif_off = notifier.is_unit_notifications_off(&env.id, &unit.name);
-            let color = state_color(&unit.state, pal);
-            let indicator = unit.state.display_indicator();
+            let color = state_color(unit.style_state(), pal);
+            let indicator = unit.style_state().display_indicator();
 
             let name_color = if is_muted { pal.muted } else { pal.fg };
 
@@ -772,15 +926,52 @@ fn env_card(
                     Some(p) => format!(":{p}"),
                     None => String::new(),
                 };
-                container(text(label).size(11).color(cyan).font(MONO))
-                    .width(port_col_w)
-                    .into()
+                let port_text: Element<'static, Message> = match env.port_conflict_for(&unit.name) {
+                    Some(conflict) => tooltip(
+                        text(label).size(11).color(pal.red).font(MONO),
+                        tip_bubble(conflict.describe(), pal),
+                        tooltip::Position::Top,
+                    )
+                    .into(),
+                    None => text(label).size(11).color(cyan).font(MONO).into(),
+                };
+                let mut cell = row![port_text];
+                if let Some(up) = env.listening_for(&unit.name) {
+                    let (glyph, tip) = if up {
+                        ("\u{2713}", "listening")
+                    } else {
+                        ("\u{2717}", "not listening")
+                    };
+                    let marker_color = match (up, &unit.state) {
+                        (true, _) => pal.green,
+                        (false, State::Running | State::Ready) => pal.red,
+                        (false, _) => pal.gray,
+                    };
+                    cell = cell.push(tooltip(
+                        text(glyph).size(11).color(marker_color).font(MONO),
+                        tip_bubble(tip, pal),
+                        tooltip::Position::Top,
+                    ));
+                }
+                container(cell).width(port_col_w).into()
             } else {
                 text("").into()
             };
 
-            let state_cell =
-                container(text(unit.state.to_string()).size(12).color(color)).width(state_col_w);
+            let state_cell = container(
+                text(unit.observed_state().to_string())
+                    .size(12)
+                    .color(color),
+            )
+            .width(state_col_w);
+
+            let since_cell = container(
+                text(unit.since_string().unwrap_or_default())
+                    .size(11)
+                    .color(muted_color)
+                    .font(MONO),
+            )
+            .width(since_col_w);
 
             let unit_mute_tip = if is_muted {
                 format!("Unmute {}", unit.name)
@@ -828,22 +1019,92 @@ fn env_card(
                 name_cell,
                 port_cell,
                 state_cell,
+                since_cell,
             ]
             .spacing(4)
             .align_y(iced::Alignment::Center);
 
+            if let Some(health) = env.health_for(&unit.name) {
+                let health_color = if health.ok() { pal.green } else { pal.red };
+                let url = env
+                    .health_checks
+                    .get(&unit.name)
+                    .map(|c| c.url.clone())
+                    .unwrap_or_default();
+                unit_row = unit_row.push(
+                    tooltip(
+                        text(health.label()).size(11).color(health_color),
+                        tip_bubble(url, pal),
+                        tooltip::Position::Top,
+                    )
+                    .gap(4),
+                );
+            }
+
+            if let Some(summary) = notifier.stats().latest(&env.dir, &unit.name) {
+                let stats_color = if summary.regression().is_some() {
+                    pal.yellow
+                } else {
+                    muted_color
+                };
+                let tip = format!(
+                    "{}: median {} · p90 {} · last {} ({} runs)",
+                    summary.phase,
+                    model::format_duration(summary.median),
+                    model::format_duration(summary.p90),
+                    model::format_duration(summary.last),
+                    summary.count
+                );
+                unit_row = unit_row.push(
+                    tooltip(
+                        text(summary.label()).size(11).color(stats_color),
+                        tip_bubble(tip, pal),
+                        tooltip::Position::Top,
+                    )
+                    .gap(4),
+                );
+            }
+
+            if let Some(progress) = unit.display_progress() {
+                let track = pal.card_border;
+                unit_row = unit_row.push(
+                    progress_bar(0.0..=1.0, progress.fraction())
+                        .width(60.0)
+                        .height(4.0)
+                        .style(move |_theme| progress_bar::Style {
+                            background: iced::Background::Color(track),
+                            bar: iced::Background::Color(color),
+                            border: iced::Border::default().rounded(2.0),
+                        }),
+                );
+                unit_row = unit_row.push(
+                    text(progress.to_string())
+                        .size(11)
+                        .color(muted_color)
+                        .font(MONO),
+                );
+            }
+
             if let Some(ref detail) = unit.detail {
                 unit_row = unit_row.push(text(detail.clone()).size(11).color(muted_color));
             }
 
-            // "open" link for units with ports
-            if let Some(port) = env.port_for(&unit.name) {
+            // Links: named ones as "label ↗", the default as a bare "↗"
+            let links = env.links_for(&unit.name);
+            if !links.is_empty() {
                 unit_row = unit_row.push(iced::widget::horizontal_space());
+            }
+            for link in links {
+                let label = match &link.label {
+                    Some(label) => format!("{label} \u{2197}"),
+                    None => "\u{2197}".to_string(),
+                };
+                let size = if link.label.is_some() { 11 } else { 13 };
                 unit_row = unit_row.push(
                     tooltip(
-                        mouse_area(text("\u{2197}").size(13).color(cyan))
-                            .on_press(Message::OpenBrowser { port }),
-                        tip_bubble("Open in browser", pal),
+                        mouse_area(text(label).size(size).color(cyan))
+                            .on_press(Message::OpenUrl(link.url.clone())),
+                        tip_bubble(link.url.clone(), pal),
                         tooltip::Position::Top,
                     )
                     .gap(4),
@@ -932,10 +1193,13 @@ fn theme(app: &App) -> Theme {
     }
 }
 
-fn subscription(_app: &App) -> Subscription<Message> {
+fn subscription(app: &App) -> Subscription<Message> {
     let tick = iced::time::every(std::time::Duration::from_secs(2)).map(|_| Message::Tick);
 
-    let watcher = Subscription::run(watch_registry);
+    // Keyed by the directory so the watcher stream is only restarted if
+    // the directory itself changes (it doesn't, today).
+    let watcher =
+        Subscription::run_with_id(app.state_dir.clone(), watch_registry(app.state_dir.clone()));
 
     let keyboard = iced::keyboard::on_key_press(|key, modifiers| {
         if modifiers.command() {
@@ -963,12 +1227,12 @@ fn subscription(_app: &App) -> Subscription<Message> {
     Subscription::batch([tick, watcher, keyboard])
 }
 
-fn watch_registry() -> impl iced::futures::Stream<Item = Message> {
+fn watch_registry(state_dir: PathBuf) -> impl iced::futures::Stream<Item = Message> {
     iced::stream::channel(32, |mut sender| async move {
         use iced::futures::SinkExt;
         use iced::futures::StreamExt;
 
-        let Ok(watcher) = RegistryWatcher::new() else {
+        let Ok(watcher) = RegistryWatcher::new(&state_dir) else {
             std::future::pending::<()>().await;
             return;
         };
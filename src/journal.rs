@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use nix::fcntl::{Flock, FlockArg};
+use serde::{Deserialize, Serialize};
+
+use crate::model::{self, State};
+
+/// File name of the live journal inside the state directory.
+pub const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// How much of the end of the live journal [`Journal::append`] checks for
+/// entries another dashboard already recorded.
+const DEDUP_WINDOW: u64 = 64 * 1024;
+
+/// Rotate once the live journal reaches this size.
+pub const DEFAULT_MAX_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Rotated generations kept next to the live file (`journal.jsonl.1` is
+/// the newest).
+pub const DEFAULT_KEEP: usize = 3;
+
+/// One observed state transition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Unix epoch seconds when the transition was observed.
+    pub ts: u64,
+    /// Environment ID.
+    pub env: String,
+    /// Environment `DIR`.
+    pub dir: PathBuf,
+    pub unit: String,
+    /// `None` when the unit first appeared.
+    pub old: Option<State>,
+    pub new: State,
+    pub detail: Option<String>,
+    /// When the unit entered `new` according to the registry (`SINCE` or
+    /// the status file's mtime), so every dashboard watching the same
+    /// registry records the same value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+}
+
+impl Entry {
+    /// Identifies a transition across dashboards; `None` without `since`.
+    fn key(&self) -> Option<(&str, &str, &State, u64)> {
+        Some((&self.env, &self.unit, &self.new, self.since?))
+    }
+}
+
+/// Append-only JSONL log of transitions, one [`Entry`] per line, with
+/// size-based rotation.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl Journal {
+    pub fn new(path: PathBuf) -> Journal {
+        Journal {
+            path,
+            max_bytes: DEFAULT_MAX_BYTES,
+            keep: DEFAULT_KEEP,
+        }
+    }
+
+    /// The journal for the registry at `state_dir`; see [`path_for`].
+    pub fn for_state_dir(state_dir: &Path) -> Option<Journal> {
+        path_for(state_dir).map(Journal::new)
+    }
+
+    pub fn with_limits(mut self, max_bytes: u64, keep: usize) -> Journal {
+        self.max_bytes = max_bytes;
+        self.keep = keep;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `entries`, rotating first if the live file is full.
+    ///
+    /// Every dashboard watching a registry observes the same transitions,
+    /// so appends hold an exclusive lock on `<journal>.lock` and skip
+    /// entries whose env, unit, state and `since` are already near the end
+    /// of the journal. The lock also keeps rotation from racing.
+    pub fn append(&self, entries: &[Entry]) -> io::Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let _lock = self.lock()?;
+        if fs::metadata(&self.path).is_ok_and(|m| m.len() >= self.max_bytes) {
+            self.rotate()?;
+        }
+        let recent = self.tail()?;
+        let seen: Vec<_> = recent.iter().filter_map(Entry::key).collect();
+        let mut buf = Vec::new();
+        for entry in entries {
+            if entry.key().is_some_and(|key| seen.contains(&key)) {
+                continue;
+            }
+            serde_json::to_writer(&mut buf, entry)?;
+            buf.push(b'\n');
+        }
+        if buf.is_empty() {
+            return Ok(());
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(&buf)
+    }
+
+    /// Block until no other process is appending. Released on drop.
+    fn lock(&self) -> io::Result<Flock<File>> {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(".lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(PathBuf::from(name))?;
+        Flock::lock(file, FlockArg::LockExclusive).map_err(|(_, errno)| errno.into())
+    }
+
+    /// The entries in the last [`DEDUP_WINDOW`] bytes of the live file.
+    fn tail(&self) -> io::Result<Vec<Entry>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let start = file.metadata()?.len().saturating_sub(DEDUP_WINDOW);
+        file.seek(SeekFrom::Start(start))?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        // Starting mid-line leaves a fragment that won't parse.
+        Ok(contents
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Every readable entry, oldest first, across rotated files. Lines
+    /// that don't parse (a torn write, a newer format) are skipped.
+    pub fn read(&self) -> Vec<Entry> {
+        let mut entries = Vec::new();
+        let files = (1..=self.keep)
+            .rev()
+            .map(|n| self.rotated(n))
+            .chain([self.path.clone()]);
+        for file in files {
+            let Ok(contents) = fs::read_to_string(&file) else {
+                continue;
+            };
+            entries.extend(
+                contents
+                    .lines()
+                    .filter_map(|line| serde_json::from_str::<Entry>(line).ok()),
+            );
+        }
+        entries
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
+    }
+
+    /// Shift `journal.jsonl.N` to `.N+1`, dropping the oldest, and move
+    /// the live file to `.1`.
+    fn rotate(&self) -> io::Result<()> {
+        if self.keep == 0 {
+            return fs::remove_file(&self.path);
+        }
+        for n in (1..self.keep).rev() {
+            let from = self.rotated(n);
+            if from.exists() {
+                fs::rename(from, self.rotated(n + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))
+    }
+}
+
+/// For each entry, how long its unit had been in `old`: the time since the
+/// unit's previous entry, or `None` if that isn't in `entries`. Expects
+/// entries oldest first, as [`Journal::read`] returns them.
+pub fn durations(entries: &[Entry]) -> Vec<Option<u64>> {
+    let mut last_seen: HashMap<(&str, &str), u64> = HashMap::new();
+    entries
+        .iter()
+        .map(|e| {
+            let prev = last_seen.insert((&e.env, &e.unit), e.ts);
+            prev.map(|prev| e.ts.saturating_sub(prev))
+        })
+        .collect()
+}
+
+/// Where the journal for the registry at `state_dir` lives:
+/// [`default_path`] for the default registry, else `journal-<id>.jsonl`
+/// next to it, `<id>` being the state dir's ID as [`model::env_id_for`]
+/// derives it. A sandbox or test registry (`--state-dir`,
+/// `SUTRA_STATE_DIR`) thus keeps its history out of the real one, and the
+/// registry directory only ever holds meta and status files.
+pub fn path_for(state_dir: &Path) -> Option<PathBuf> {
+    let default = default_path()?;
+    if model::default_state_dir().is_some_and(|d| d == state_dir) {
+        return Some(default);
+    }
+    let id = model::env_id_for(state_dir).unwrap_or_else(|_| model::path_id(state_dir));
+    Some(default.with_file_name(format!("journal-{id}.jsonl")))
+}
+
+/// `$XDG_STATE_HOME/sutra/journal.jsonl`, defaulting to
+/// `~/.local/state/sutra/journal.jsonl` on every platform.
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| dirs::home_dir().map(|h| h.join(".local").join("state")))?;
+    Some(base.join("sutra").join(JOURNAL_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(ts: u64, new: State) -> Entry {
+        Entry {
+            ts,
+            env: "ab12".into(),
+            dir: PathBuf::from("/code/app"),
+            unit: "server".into(),
+            old: Some(State::Building),
+            new,
+            detail: Some("exit code 1".into()),
+            since: None,
+        }
+    }
+
+    #[test]
+    fn appends_and_reads_back_in_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let journal = Journal::new(tmp.path().join("nested").join(JOURNAL_FILE));
+        journal.append(&[entry(1, State::Failed)]).unwrap();
+        journal.append(&[entry(2, State::Ready)]).unwrap();
+        fs::write(
+            journal.path(),
+            fs::read_to_string(journal.path()).unwrap() + "{not json\n",
+        )
+        .unwrap();
+
+        let entries = journal.read();
+        assert_eq!(entries, [entry(1, State::Failed), entry(2, State::Ready)]);
+        let first = fs::read_to_string(journal.path()).unwrap();
+        assert!(first.starts_with(r#"{"ts":1,"env":"ab12","dir":"/code/app","unit":"server","old":"building","new":"failed""#));
+    }
+
+    #[test]
+    fn durations_are_per_unit() {
+        let mut worker = entry(15, State::Ready);
+        worker.unit = "worker".into();
+        let entries = [
+            entry(10, State::Building),
+            worker,
+            entry(40, State::Failed),
+            entry(45, State::Building),
+        ];
+        assert_eq!(durations(&entries), [None, None, Some(30), Some(5)]);
+    }
+
+    #[test]
+    fn skips_transitions_already_recorded() {
+        let tmp = tempfile::tempdir().unwrap();
+        let journal = Journal::new(tmp.path().join(JOURNAL_FILE));
+        let mut failed = entry(10, State::Failed);
+        failed.since = Some(9);
+        journal.append(&[failed.clone()]).unwrap();
+        // A second dashboard saw the same transition a second later.
+        let mut again = failed.clone();
+        again.ts = 11;
+        let mut ready = entry(11, State::Ready);
+        ready.since = Some(11);
+        journal.append(&[again, ready.clone()]).unwrap();
+        assert_eq!(journal.read(), [failed, ready]);
+        assert!(tmp.path().join("journal.jsonl.lock").exists());
+    }
+
+    #[test]
+    fn sandbox_registries_get_their_own_journal() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = path_for(tmp.path()).unwrap();
+        let id = model::env_id_for(tmp.path()).unwrap();
+        assert_eq!(path.file_name().unwrap(), &*format!("journal-{id}.jsonl"));
+        assert_eq!(path.parent(), default_path().unwrap().parent());
+    }
+
+    #[test]
+    fn rotation_caps_generations() {
+        let tmp = tempfile::tempdir().unwrap();
+        let journal = Journal::new(tmp.path().join(JOURNAL_FILE)).with_limits(1, 2);
+        for ts in 0..5 {
+            journal.append(&[entry(ts, State::Ready)]).unwrap();
+        }
+        let ts: Vec<u64> = journal.read().iter().map(|e| e.ts).collect();
+        assert_eq!(ts, [2, 3, 4]);
+        assert!(!tmp.path().join("journal.jsonl.3").exists());
+    }
+}
@@ -1,6 +1,16 @@
+pub mod browser;
+pub mod config;
+pub mod journal;
+pub mod liveness;
 pub mod model;
 pub mod notifications;
+pub mod patterns;
+pub mod probe;
+pub mod sockets;
+pub mod stats;
+pub mod supervisor;
 pub mod watcher;
+pub mod writer;
 
 #[cfg(feature = "tui")]
 pub mod tui;
@@ -0,0 +1,464 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use nix::sys::signal;
+use nix::unistd::Pid;
+
+/// Longest the monitor thread blocks before picking up new PIDs to watch.
+const MONITOR_WAKE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often PIDs without kernel exit notification are checked with
+/// `kill(pid, 0)`.
+const MONITOR_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Slack when comparing a process's start time to `STARTED`. Linux
+/// derives start times from boot time and clock ticks, which can round a
+/// second either way.
+const START_SLACK_SECS: u64 = 2;
+
+/// Whether an environment's supervisor PID is still the process that
+/// registered it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liveness {
+    Alive,
+    Dead,
+    /// The PID exists but belongs to a process that started after the
+    /// environment registered — it was reused after a reboot or wraparound.
+    StalePid,
+}
+
+/// Check `pid` against the environment's `STARTED` time.
+///
+/// A registering process is always older than its registration, so a
+/// process that started later can't be the one that wrote the meta file.
+/// Without `started`, or where the start time can't be read, this falls
+/// back to plain existence.
+pub fn check(pid: u32, started: Option<u64>) -> Liveness {
+    if !pid_exists(pid) {
+        return Liveness::Dead;
+    }
+    match (started, process_start_time(pid)) {
+        (Some(started), Some(start)) if start > started + START_SLACK_SECS => Liveness::StalePid,
+        _ => Liveness::Alive,
+    }
+}
+
+/// True if a process with `pid` exists (`kill(pid, 0)` succeeds).
+pub fn pid_exists(pid: u32) -> bool {
+    match i32::try_from(pid) {
+        Ok(raw_pid) => signal::kill(Pid::from_raw(raw_pid), None).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// When `pid` started, in epoch seconds: field 22 of `/proc/<pid>/stat`
+/// (clock ticks since boot) plus `btime` from `/proc/stat`.
+#[cfg(target_os = "linux")]
+pub fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // `comm` is parenthesized and may itself contain spaces or ')'.
+    let fields = &stat[stat.rfind(')')? + 1..];
+    // The fields after `comm` start at field 3.
+    let ticks: u64 = fields.split_whitespace().nth(22 - 3)?.parse().ok()?;
+    // SAFETY: sysconf has no preconditions.
+    let hz = unsafe { nix::libc::sysconf(nix::libc::_SC_CLK_TCK) };
+    let hz = u64::try_from(hz).ok().filter(|&hz| hz > 0)?;
+    Some(boot_time()? + ticks / hz)
+}
+
+#[cfg(target_os = "linux")]
+fn boot_time() -> Option<u64> {
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    stat.lines()
+        .find_map(|line| line.strip_prefix("btime "))?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// When `pid` started, in epoch seconds, from `proc_pidinfo` — the same
+/// `p_starttime` that `sysctl kern.proc.pid.<pid>` reports.
+#[cfg(target_os = "macos")]
+pub fn process_start_time(pid: u32) -> Option<u64> {
+    use nix::libc;
+
+    let pid = i32::try_from(pid).ok()?;
+    let mut info: libc::proc_bsdinfo = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::proc_bsdinfo>() as libc::c_int;
+    // SAFETY: `info` is a properly sized, writable proc_bsdinfo.
+    let written = unsafe {
+        libc::proc_pidinfo(
+            pid,
+            libc::PROC_PIDTBSDINFO,
+            0,
+            (&mut info as *mut libc::proc_bsdinfo).cast(),
+            size,
+        )
+    };
+    (written == size).then_some(info.pbi_start_tvsec)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn process_start_time(_pid: u32) -> Option<u64> {
+    None
+}
+
+enum Command {
+    Watch(String, u32),
+    Unwatch(String),
+}
+
+/// Reports environment PIDs exiting as it happens, rather than on the next
+/// `kill(pid, 0)` poll.
+///
+/// Uses a pidfd per PID on Linux and a kqueue `EVFILT_PROC` filter on
+/// macOS, both waited on by one background thread. PIDs the kernel can't
+/// watch (pidfd needs Linux 5.3) fall back to polling. The thread stops
+/// once every handle is dropped.
+#[derive(Clone)]
+pub struct ExitMonitor {
+    tx: mpsc::Sender<Command>,
+}
+
+impl ExitMonitor {
+    /// Start the monitor. `on_exit` runs on its thread with the ID of each
+    /// environment whose PID exits, once per [`ExitMonitor::watch`].
+    pub fn spawn(on_exit: impl Fn(String) + Send + 'static) -> ExitMonitor {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || monitor_loop(rx, on_exit));
+        ExitMonitor { tx }
+    }
+
+    /// Watch `pid` for environment `id`, replacing whatever was watched
+    /// for it before.
+    pub fn watch(&self, id: &str, pid: u32) {
+        let _ = self.tx.send(Command::Watch(id.to_string(), pid));
+    }
+
+    pub fn unwatch(&self, id: &str) {
+        let _ = self.tx.send(Command::Unwatch(id.to_string()));
+    }
+}
+
+/// Result of asking the kernel to watch a PID.
+enum Added {
+    Watching,
+    /// The process is already gone.
+    Gone,
+    /// No kernel support; poll instead.
+    Unsupported,
+}
+
+/// Kernel exit notification for a set of PIDs.
+trait Native {
+    fn add(&mut self, id: &str, pid: u32) -> Added;
+    fn remove(&mut self, id: &str);
+    /// Block up to `timeout`; return the IDs whose process exited.
+    fn wait(&mut self, timeout: Duration) -> Vec<String>;
+}
+
+fn monitor_loop(rx: mpsc::Receiver<Command>, on_exit: impl Fn(String)) {
+    let mut native = native();
+    let mut polled: HashMap<String, u32> = HashMap::new();
+    let mut last_poll = Instant::now();
+    loop {
+        let mut exited = Vec::new();
+        loop {
+            let command = match rx.try_recv() {
+                Ok(command) => command,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return,
+            };
+            let id = match &command {
+                Command::Watch(id, _) | Command::Unwatch(id) => id.clone(),
+            };
+            polled.remove(&id);
+            if let Some(native) = native.as_mut() {
+                native.remove(&id);
+            }
+            let Command::Watch(id, pid) = command else {
+                continue;
+            };
+            match native
+                .as_mut()
+                .map_or(Added::Unsupported, |n| n.add(&id, pid))
+            {
+                Added::Watching => {}
+                Added::Gone => exited.push(id),
+                Added::Unsupported => {
+                    polled.insert(id, pid);
+                }
+            }
+        }
+
+        match native.as_mut() {
+            Some(native) => exited.extend(native.wait(MONITOR_WAKE_INTERVAL)),
+            None => thread::sleep(MONITOR_WAKE_INTERVAL),
+        }
+        if last_poll.elapsed() >= MONITOR_POLL_INTERVAL {
+            last_poll = Instant::now();
+            polled.retain(|id, pid| {
+                let alive = pid_exists(*pid);
+                if !alive {
+                    exited.push(id.clone());
+                }
+                alive
+            });
+        }
+
+        for id in exited {
+            on_exit(id);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn native() -> Option<Box<dyn Native>> {
+    Some(Box::new(pidfd::Pidfds::default()))
+}
+
+#[cfg(target_os = "macos")]
+fn native() -> Option<Box<dyn Native>> {
+    kqueue::Kqueue::new().map(|kq| Box::new(kq) as Box<dyn Native>)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn native() -> Option<Box<dyn Native>> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+mod pidfd {
+    use std::collections::HashMap;
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::time::Duration;
+
+    use nix::libc;
+
+    use super::{Added, Native};
+
+    /// One pidfd per watched PID; a pidfd polls readable once its
+    /// process exits.
+    #[derive(Default)]
+    pub struct Pidfds {
+        fds: HashMap<String, OwnedFd>,
+    }
+
+    impl Native for Pidfds {
+        fn add(&mut self, id: &str, pid: u32) -> Added {
+            match pidfd_open(pid) {
+                Ok(fd) => {
+                    self.fds.insert(id.to_string(), fd);
+                    Added::Watching
+                }
+                Err(e) if e.raw_os_error() == Some(libc::ESRCH) => Added::Gone,
+                Err(_) => Added::Unsupported,
+            }
+        }
+
+        fn remove(&mut self, id: &str) {
+            self.fds.remove(id);
+        }
+
+        fn wait(&mut self, timeout: Duration) -> Vec<String> {
+            let ids: Vec<&String> = self.fds.keys().collect();
+            let mut pollfds: Vec<libc::pollfd> = self
+                .fds
+                .values()
+                .map(|fd| libc::pollfd {
+                    fd: fd.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                })
+                .collect();
+            // SAFETY: `pollfds` is a valid, correctly sized array of
+            // pollfd; with none, poll just sleeps for the timeout.
+            let ready = unsafe {
+                libc::poll(
+                    pollfds.as_mut_ptr(),
+                    pollfds.len() as libc::nfds_t,
+                    timeout.as_millis() as libc::c_int,
+                )
+            };
+            if ready <= 0 {
+                return Vec::new();
+            }
+            let exited: Vec<String> = ids
+                .into_iter()
+                .zip(&pollfds)
+                .filter(|(_, p)| p.revents != 0)
+                .map(|(id, _)| id.clone())
+                .collect();
+            for id in &exited {
+                self.fds.remove(id);
+            }
+            exited
+        }
+    }
+
+    fn pidfd_open(pid: u32) -> io::Result<OwnedFd> {
+        let pid =
+            libc::pid_t::try_from(pid).map_err(|_| io::Error::from_raw_os_error(libc::ESRCH))?;
+        // SAFETY: pidfd_open takes a PID and flags and returns a new
+        // descriptor or -1.
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `fd` was just opened and nothing else owns it.
+        Ok(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) })
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod kqueue {
+    use std::collections::HashMap;
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::ptr;
+    use std::time::Duration;
+
+    use nix::libc;
+
+    use super::{Added, Native};
+
+    /// A kqueue with a one-shot `EVFILT_PROC`/`NOTE_EXIT` filter per PID.
+    pub struct Kqueue {
+        fd: OwnedFd,
+        ids: HashMap<u32, String>,
+    }
+
+    impl Kqueue {
+        pub fn new() -> Option<Kqueue> {
+            // SAFETY: kqueue takes no arguments and returns a new
+            // descriptor or -1.
+            let fd = unsafe { libc::kqueue() };
+            if fd < 0 {
+                return None;
+            }
+            Some(Kqueue {
+                // SAFETY: `fd` was just opened and nothing else owns it.
+                fd: unsafe { OwnedFd::from_raw_fd(fd) },
+                ids: HashMap::new(),
+            })
+        }
+
+        fn change(&self, pid: u32, flags: u16) -> io::Result<()> {
+            let change = libc::kevent {
+                ident: pid as libc::uintptr_t,
+                filter: libc::EVFILT_PROC,
+                flags,
+                fflags: libc::NOTE_EXIT,
+                data: 0,
+                udata: ptr::null_mut(),
+            };
+            // SAFETY: one valid changelist entry, no event list.
+            let r = unsafe {
+                libc::kevent(
+                    self.fd.as_raw_fd(),
+                    &change,
+                    1,
+                    ptr::null_mut(),
+                    0,
+                    ptr::null(),
+                )
+            };
+            if r < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+    }
+
+    impl Native for Kqueue {
+        fn add(&mut self, id: &str, pid: u32) -> Added {
+            match self.change(pid, libc::EV_ADD | libc::EV_ONESHOT) {
+                Ok(()) => {
+                    self.ids.insert(pid, id.to_string());
+                    Added::Watching
+                }
+                Err(e) if e.raw_os_error() == Some(libc::ESRCH) => Added::Gone,
+                Err(_) => Added::Unsupported,
+            }
+        }
+
+        fn remove(&mut self, id: &str) {
+            let pids: Vec<u32> = self
+                .ids
+                .iter()
+                .filter(|(_, i)| i.as_str() == id)
+                .map(|(pid, _)| *pid)
+                .collect();
+            for pid in pids {
+                self.ids.remove(&pid);
+                let _ = self.change(pid, libc::EV_DELETE);
+            }
+        }
+
+        fn wait(&mut self, timeout: Duration) -> Vec<String> {
+            // SAFETY: kevent is plain data; all-zero is a valid value.
+            let mut events: [libc::kevent; 16] = unsafe { std::mem::zeroed() };
+            let ts = libc::timespec {
+                tv_sec: timeout.as_secs() as libc::time_t,
+                tv_nsec: timeout.subsec_nanos() as libc::c_long,
+            };
+            // SAFETY: `events` is a writable array of the length passed.
+            let n = unsafe {
+                libc::kevent(
+                    self.fd.as_raw_fd(),
+                    ptr::null(),
+                    0,
+                    events.as_mut_ptr(),
+                    events.len() as libc::c_int,
+                    &ts,
+                )
+            };
+            let n = usize::try_from(n).unwrap_or(0);
+            events[..n]
+                .iter()
+                .filter(|ev| ev.filter == libc::EVFILT_PROC && ev.fflags & libc::NOTE_EXIT != 0)
+                .filter_map(|ev| self.ids.remove(&(ev.ident as u32)))
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::now_secs;
+
+    #[test]
+    fn monitor_reports_exit() {
+        let (tx, rx) = mpsc::channel();
+        let monitor = ExitMonitor::spawn(move |id| {
+            let _ = tx.send(id);
+        });
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        monitor.watch("ab12", child.id());
+        monitor.watch("cd34", u32::MAX);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), "cd34");
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), "ab12");
+    }
+
+    #[test]
+    fn reused_pid_is_stale() {
+        let me = std::process::id();
+        assert_eq!(check(me, Some(now_secs())), Liveness::Alive);
+        assert_eq!(check(me, None), Liveness::Alive);
+        assert_eq!(check(u32::MAX, Some(now_secs())), Liveness::Dead);
+        // A registration older than this process can't have been made by it.
+        if process_start_time(me).is_some() {
+            assert_eq!(check(me, Some(1_000_000)), Liveness::StalePid);
+        }
+    }
+}
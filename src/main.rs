@@ -1,8 +1,34 @@
-use clap::{Parser, Subcommand};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
+
+use clap::{Args, Parser, Subcommand};
+use sutra::journal::{self, Journal};
+use sutra::model::{self, Environment, Meta, Progress, State, UnitStatus};
+use sutra::patterns::Rule;
+use sutra::stats::{Stats, Summary};
+use sutra::supervisor::{self, Registration, UnitSpec};
+use sutra::watcher::RegistryWatcher;
+use sutra::writer;
+
+/// `sutra wait` exit code when a unit fails or the environment dies.
+const EXIT_WAIT_FAILED: i32 = 2;
+/// `sutra wait` exit code on timeout (same as coreutils `timeout`).
+const EXIT_WAIT_TIMEOUT: i32 = 124;
+
+/// How often `sutra wait` re-checks PID liveness between filesystem
+/// events, since a dying supervisor doesn't necessarily touch the registry.
+const WAIT_LIVENESS_INTERVAL: Duration = Duration::from_secs(1);
 
 #[derive(Parser)]
 #[command(name = "sutra", about = "Dev environment status & orchestration")]
 struct Cli {
+    /// Registry directory to read and write. Overrides $SUTRA_STATE_DIR
+    /// and $DEV_RUNNER_DIR; defaults to ~/.dev-runner
+    #[arg(long, global = true, value_name = "DIR")]
+    state_dir: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Option<Command>,
 }
@@ -19,24 +45,324 @@ enum Command {
         #[arg(long)]
         foreground: bool,
     },
+
+    /// Read or publish unit status
+    Status {
+        #[command(subcommand)]
+        command: StatusCommand,
+    },
+
+    /// Write an environment's meta file and print its ID
+    Register {
+        /// Project directory
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+
+        /// Supervisor PID; defaults to the calling process (e.g. the shell's $$)
+        #[arg(long)]
+        pid: Option<u32>,
+
+        /// Declare a unit's port, e.g. --port server=3000 (repeatable)
+        #[arg(long = "port", value_name = "UNIT=PORT", value_parser = parse_port_arg)]
+        ports: Vec<(String, u16)>,
+
+        /// Environment ID; derived from --dir when omitted
+        #[arg(long)]
+        id: Option<String>,
+
+        /// Display name (default: the directory name)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// One-line description shown under the name
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Emoji or short glyph shown before the name
+        #[arg(long)]
+        icon: Option<String>,
+
+        /// Overwrite an existing registration even if its PID is alive
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Remove an environment's meta file and all of its status files
+    Unregister {
+        /// Environment ID, as printed by `sutra register`
+        id: String,
+    },
+
+    /// Remove dead environments and orphaned status files.
+    ///
+    /// An environment is dead when its PID is gone; its meta file and its
+    /// status files (both conventions) are removed. Status files with no
+    /// meta file are removed too.
+    Gc {
+        /// Only list what would be removed
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Skip anything modified more recently than this, e.g. 1h or 2d
+        #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration, default_value = "0s")]
+        older_than: Duration,
+    },
+
+    /// Print the canonical environment ID for a project directory
+    Id {
+        /// Project directory; defaults to the current directory
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+
+    /// List registered environments and their units
+    Ls {
+        /// Only show the environment with this ID, or the one containing
+        /// this directory
+        target: Option<String>,
+
+        /// Print a JSON array
+        #[arg(long, conflicts_with = "ndjson")]
+        json: bool,
+
+        /// Print one JSON object per line
+        #[arg(long)]
+        ndjson: bool,
+    },
+
+    /// Block until units reach the given states.
+    ///
+    /// Exits 0 once every condition holds, 2 if a unit enters `failed`,
+    /// a unit's PID dies or the environment dies while being waited on,
+    /// and 124 on timeout. A leftover dead registration is waited on like
+    /// one that isn't there yet.
+    Wait {
+        /// Conditions like server=ready vite=ready
+        #[arg(required = true, value_name = "UNIT=STATE", value_parser = parse_condition_arg)]
+        conditions: Vec<(String, State)>,
+
+        /// Environment ID or a directory inside the project; defaults to
+        /// the current directory
+        #[arg(long, value_name = "ID|DIR")]
+        env: Option<String>,
+
+        /// Give up after this long, e.g. 120s or 5m (default: wait forever)
+        #[arg(long, value_parser = humantime::parse_duration)]
+        timeout: Option<Duration>,
+    },
+
+    /// Show past state transitions from the journal, oldest first.
+    ///
+    /// Transitions are recorded while `sutra mon` is open. Each line shows
+    /// how long the unit had been in its previous state.
+    History {
+        /// Environment ID or a directory inside the project
+        #[arg(long, value_name = "ID|DIR")]
+        env: Option<String>,
+
+        /// Only this unit
+        #[arg(long)]
+        unit: Option<String>,
+
+        /// Only transitions in the last DURATION, e.g. 1h or 30m
+        #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+        since: Option<Duration>,
+
+        /// Only transitions into this state
+        #[arg(long, value_parser = State::from_user_str)]
+        state: Option<State>,
+
+        /// Print a JSON array
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show build and startup durations per unit: median, p90 and last.
+    ///
+    /// Computed from the journal. A last run at least twice the median
+    /// is flagged as a regression.
+    Stats {
+        /// Environment ID or a directory inside the project
+        #[arg(long, value_name = "ID|DIR")]
+        env: Option<String>,
+
+        /// Print a JSON array
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Run a command as a unit, publishing its status until it exits.
+    ///
+    /// Registers the environment (or joins the one already registered for
+    /// $SUTRA_ENV_ID / the project directory), forwards SIGTERM/SIGINT to
+    /// the command and exits with its exit code.
+    Run {
+        /// Unit name (e.g. server); must not contain '.'
+        #[arg(long)]
+        unit: String,
+
+        /// Declare a unit's port, e.g. --port server=3000 (repeatable)
+        #[arg(long = "port", value_name = "UNIT=PORT", value_parser = parse_port_arg)]
+        ports: Vec<(String, u16)>,
+
+        /// Project directory
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+
+        /// Command to run, after `--`
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+
+    /// Run a command as a unit, inferring its state from its output.
+    ///
+    /// Like `run`, but stdout/stderr are passed through line by line and
+    /// each line is matched against the --rule patterns, first match wins.
+    /// The matched line becomes the status detail.
+    Tap {
+        /// Unit name (e.g. vite); must not contain '.'
+        #[arg(long)]
+        unit: String,
+
+        /// Pattern like ready='Local:.*http://' (repeatable)
+        #[arg(long = "rule", value_name = "STATE=REGEX", value_parser = Rule::parse)]
+        rules: Vec<Rule>,
+
+        /// Declare a unit's port, e.g. --port vite=5173 (repeatable)
+        #[arg(long = "port", value_name = "UNIT=PORT", value_parser = parse_port_arg)]
+        ports: Vec<(String, u16)>,
+
+        /// Project directory
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+
+        /// Command to run, after `--`
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+
+    /// Start the units declared in sutra.toml or a Procfile.
+    ///
+    /// Each unit runs in its own process group with its output prefixed
+    /// by the unit name. SIGTERM/SIGINT tears all of them down.
+    Up {
+        /// Only start these units (default: all)
+        units: Vec<String>,
+
+        /// Config file; defaults to ./sutra.toml, then ./Procfile
+        #[arg(long, short = 'f', value_name = "PATH")]
+        file: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum StatusCommand {
+    /// Atomically write a unit's status file
+    Set {
+        /// Unit name (e.g. server, vite); must not contain '.'
+        unit: String,
+
+        /// State keyword, e.g. ready or failed; unknown states are rejected
+        state: String,
+
+        /// Optional freeform detail, e.g. "exit code 1"
+        #[arg(trailing_var_arg = true)]
+        detail: Vec<String>,
+
+        #[command(flatten)]
+        fields: StatusFields,
+
+        #[command(flatten)]
+        target: EnvTarget,
+    },
+}
+
+/// Optional v2 status fields, written as `KEY=VALUE` lines after the
+/// state line.
+#[derive(Args)]
+struct StatusFields {
+    /// Progress as CURRENT/TOTAL or PERCENT%, e.g. 42/120
+    #[arg(long, value_parser = parse_progress_arg)]
+    progress: Option<Progress>,
+
+    /// Path to the unit's log file
+    #[arg(long)]
+    log: Option<PathBuf>,
+
+    /// Error count, e.g. from the last build
+    #[arg(long)]
+    errors: Option<u32>,
+
+    /// PID of the unit's process, so sutra can tell when it dies
+    #[arg(long)]
+    pid: Option<u32>,
+}
+
+/// Selects which registered environment a command applies to.
+#[derive(Args)]
+struct EnvTarget {
+    /// Environment ID (the meta filename)
+    #[arg(long, env = "SUTRA_ENV_ID", conflicts_with = "dir")]
+    env_id: Option<String>,
+
+    /// Project directory (or a subdirectory of it) of a registered
+    /// environment; defaults to the current directory
+    #[arg(long)]
+    dir: Option<PathBuf>,
+}
+
+impl EnvTarget {
+    /// Resolve to an environment ID: `--env-id` as given, otherwise the
+    /// registered environment whose DIR contains `--dir` (or the cwd).
+    fn resolve(&self, state_dir: &Path) -> Result<String, String> {
+        if let Some(id) = &self.env_id {
+            if !model::is_meta_file(id) {
+                return Err(format!("invalid environment id {id:?}"));
+            }
+            return Ok(id.clone());
+        }
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => std::env::current_dir().map_err(|e| format!("current directory: {e}"))?,
+        };
+        let envs = model::load_all(state_dir);
+        model::find_for_dir(&envs, &dir)
+            .map(|env| env.id.clone())
+            .ok_or_else(|| {
+                format!(
+                    "no environment registered for {} in {}",
+                    dir.display(),
+                    state_dir.display()
+                )
+            })
+    }
 }
 
 fn main() {
     let cli = Cli::parse();
 
-    match cli.command.unwrap_or(Command::Mon {
+    let Some(state_dir) = sutra::model::resolve_state_dir(cli.state_dir.as_deref()) else {
+        eprintln!(
+            "could not determine the state directory; pass --state-dir or set SUTRA_STATE_DIR"
+        );
+        std::process::exit(1);
+    };
+
+    let result = match cli.command.unwrap_or(Command::Mon {
         tui: false,
         foreground: false,
     }) {
         Command::Mon { tui, foreground } => {
             if tui {
                 #[cfg(feature = "tui")]
-                sutra::tui::run();
+                {
+                    sutra::tui::run(state_dir);
+                    Ok(())
+                }
 
                 #[cfg(not(feature = "tui"))]
                 {
-                    eprintln!("TUI not available (compiled without 'tui' feature)");
-                    std::process::exit(1);
+                    Err("TUI not available (compiled without 'tui' feature)".to_string())
                 }
             } else {
                 // GUI mode: background by default so the shell is freed
@@ -45,21 +371,822 @@ fn main() {
                 }
 
                 #[cfg(feature = "gui")]
-                sutra::gui::run();
+                {
+                    sutra::gui::run(state_dir);
+                    Ok(())
+                }
 
                 #[cfg(all(not(feature = "gui"), feature = "tui"))]
-                sutra::tui::run();
+                {
+                    sutra::tui::run(state_dir);
+                    Ok(())
+                }
 
                 #[cfg(all(not(feature = "gui"), not(feature = "tui")))]
                 {
-                    eprintln!("No UI available (compiled without 'tui' or 'gui' features)");
-                    std::process::exit(1);
+                    let _ = state_dir;
+                    Err("No UI available (compiled without 'tui' or 'gui' features)".to_string())
+                }
+            }
+        }
+        Command::Status {
+            command:
+                StatusCommand::Set {
+                    unit,
+                    state,
+                    detail,
+                    fields,
+                    target,
+                },
+        } => status_set(
+            &state_dir,
+            &target,
+            &unit,
+            &state,
+            &detail.join(" "),
+            fields,
+        ),
+        Command::Register {
+            dir,
+            pid,
+            ports,
+            id,
+            name,
+            description,
+            icon,
+            force,
+        } => {
+            let display = DisplayKeys {
+                name,
+                description,
+                icon,
+            };
+            register(&state_dir, &dir, pid, ports, id, display, force)
+        }
+        Command::Unregister { id } => unregister(&state_dir, &id),
+        Command::Gc {
+            dry_run,
+            older_than,
+        } => gc(&state_dir, older_than, dry_run),
+        Command::Id { path } => model::env_id_for(&path)
+            .map(|id| println!("{id}"))
+            .map_err(|e| format!("{}: {e}", path.display())),
+        Command::Ls {
+            target,
+            json,
+            ndjson,
+        } => ls(&state_dir, target.as_deref(), json, ndjson),
+        Command::Wait {
+            conditions,
+            env,
+            timeout,
+        } => match wait(&state_dir, env.as_deref(), &conditions, timeout) {
+            Ok(0) => Ok(()),
+            Ok(code) => std::process::exit(code),
+            Err(e) => Err(e),
+        },
+        Command::History {
+            env,
+            unit,
+            since,
+            state,
+            json,
+        } => {
+            let filter = HistoryFilter {
+                env,
+                unit,
+                since,
+                state,
+            };
+            history(&state_dir, &filter, json)
+        }
+        Command::Stats { env, json } => stats(&state_dir, env.as_deref(), json),
+        Command::Run {
+            unit,
+            ports,
+            dir,
+            command,
+        } => run(&state_dir, &unit, Vec::new(), ports, &dir, command),
+        Command::Tap {
+            unit,
+            rules,
+            ports,
+            dir,
+            command,
+        } => {
+            if rules.is_empty() {
+                Err("tap needs at least one --rule STATE=REGEX".into())
+            } else {
+                run(&state_dir, &unit, rules, ports, &dir, command)
+            }
+        }
+        Command::Up { units, file } => up(&state_dir, &units, file),
+    };
+
+    if let Err(e) = result {
+        eprintln!("sutra: {e}");
+        std::process::exit(1);
+    }
+}
+
+/// `sutra status set`: validate, then write the status file atomically.
+fn status_set(
+    state_dir: &Path,
+    target: &EnvTarget,
+    unit: &str,
+    state: &str,
+    detail: &str,
+    fields: StatusFields,
+) -> Result<(), String> {
+    let state = State::from_user_str(state)?;
+    writer::validate_unit_name(unit).map_err(|e| e.to_string())?;
+    let id = target.resolve(state_dir)?;
+    let detail = (!detail.trim().is_empty()).then_some(detail);
+    let mut status = UnitStatus::new(unit, state, detail);
+    status.progress = fields.progress;
+    status.log = fields.log;
+    status.errors = fields.errors;
+    status.pid = fields.pid;
+    writer::write_status(state_dir, &id, &status)
+        .map_err(|e| format!("writing status for {unit}: {e}"))
+}
+
+/// `sutra register`: write the meta file and print the environment ID.
+///
+/// Stale status files left over from a crashed run under the same ID are
+/// cleared first, so the dashboard doesn't show old `ready` rows.
+fn register(
+    state_dir: &Path,
+    dir: &Path,
+    pid: Option<u32>,
+    ports: Vec<(String, u16)>,
+    id: Option<String>,
+    display: DisplayKeys,
+    force: bool,
+) -> Result<(), String> {
+    let dir = fs::canonicalize(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    let id = match id {
+        Some(id) => id,
+        None => model::env_id_for(&dir).map_err(|e| format!("{}: {e}", dir.display()))?,
+    };
+    if !model::is_meta_file(&id) {
+        return Err(format!("invalid environment id {id:?}"));
+    }
+
+    let pid = pid.unwrap_or_else(parent_pid);
+    if let Some(existing) = Environment::load(&state_dir.join(&id)) {
+        if existing.alive && existing.pid != pid && !force {
+            return Err(format!(
+                "{id} is already registered by live PID {} for {} (use --force to take over)",
+                existing.pid,
+                existing.dir.display()
+            ));
+        }
+    }
+
+    let mut meta = Meta::new(dir, pid);
+    meta.started = Some(model::now_secs());
+    meta.ports = ports.into_iter().collect();
+    meta.name = display.name;
+    meta.description = display.description;
+    meta.icon = display.icon;
+
+    writer::remove_status_files(state_dir, &id).map_err(|e| e.to_string())?;
+    writer::write_meta(state_dir, &id, &meta).map_err(|e| e.to_string())?;
+    println!("{id}");
+    Ok(())
+}
+
+/// `sutra register`'s optional `NAME` / `DESCRIPTION` / `ICON` keys.
+struct DisplayKeys {
+    name: Option<String>,
+    description: Option<String>,
+    icon: Option<String>,
+}
+
+/// `sutra unregister`: remove the meta file and both status-file
+/// conventions. The ID is validated before anything is touched, so an
+/// empty or malformed ID can never widen the cleanup to other projects.
+fn unregister(state_dir: &Path, id: &str) -> Result<(), String> {
+    if !model::is_meta_file(id) {
+        return Err(format!(
+            "refusing to unregister invalid environment id {id:?}"
+        ));
+    }
+    writer::remove_environment(state_dir, id).map_err(|e| e.to_string())
+}
+
+/// `sutra gc`: remove (or with `--dry-run`, list) dead registrations.
+fn gc(state_dir: &Path, older_than: Duration, dry_run: bool) -> Result<(), String> {
+    let report = writer::gc(state_dir, older_than, dry_run)
+        .map_err(|e| format!("{}: {e}", state_dir.display()))?;
+    if report.is_empty() {
+        println!("Nothing to clean up in {}", state_dir.display());
+        return Ok(());
+    }
+    let verb = if dry_run { "would remove" } else { "removed" };
+    for (id, dir) in &report.environments {
+        println!("{verb} dead environment {id} ({})", dir.display());
+    }
+    for path in &report.orphans {
+        println!("{verb} orphaned status file {}", path.display());
+    }
+    Ok(())
+}
+
+/// PID of the process that invoked sutra — the shell running the dev
+/// script, in the usual `sutra register` case.
+fn parent_pid() -> u32 {
+    nix::unistd::getppid().as_raw() as u32
+}
+
+/// `sutra ls`: one-shot listing of the registry.
+fn ls(state_dir: &Path, target: Option<&str>, json: bool, ndjson: bool) -> Result<(), String> {
+    let envs = model::load_all(state_dir);
+    let envs: Vec<&Environment> = match target {
+        Some(target) => {
+            vec![
+                select_env(&envs, target)
+                    .ok_or_else(|| format!("no environment matches {target:?}"))?,
+            ]
+        }
+        None => envs.iter().collect(),
+    };
+
+    if json {
+        let all: Vec<Listed> = envs.iter().map(|e| Listed::new(e)).collect();
+        let out = serde_json::to_string_pretty(&all).map_err(|e| e.to_string())?;
+        println!("{out}");
+    } else if ndjson {
+        for env in envs {
+            let out = serde_json::to_string(&Listed::new(env)).map_err(|e| e.to_string())?;
+            println!("{out}");
+        }
+    } else if envs.is_empty() {
+        println!("No environments found in {}", state_dir.display());
+    } else {
+        for (i, env) in envs.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            print_env(env);
+        }
+    }
+    Ok(())
+}
+
+/// `Environment` plus fields that are only meaningful at listing time.
+#[derive(serde::Serialize)]
+struct Listed<'a> {
+    #[serde(flatten)]
+    env: &'a Environment,
+    /// `None` when the meta file has no `STARTED`.
+    elapsed_secs: Option<u64>,
+}
+
+impl<'a> Listed<'a> {
+    fn new(env: &'a Environment) -> Self {
+        Listed {
+            env,
+            elapsed_secs: (env.started > 0).then(|| env.elapsed_secs()),
+        }
+    }
+}
+
+/// Human-readable block for one environment, laid out like a TUI card.
+fn print_env(env: &Environment) {
+    let (dot, liveness) = if env.alive {
+        ("\u{25cf}", "alive")
+    } else if env.stale_pid {
+        ("\u{25cb}", "stale PID")
+    } else {
+        ("\u{25cb}", "dead")
+    };
+    let uptime = if env.started > 0 {
+        format!("  up {}", env.elapsed_string())
+    } else {
+        String::new()
+    };
+    let icon = env
+        .icon
+        .as_deref()
+        .map(|i| format!("{i} "))
+        .unwrap_or_default();
+    println!(
+        "{dot} {icon}{}  {}  pid {} ({liveness}){uptime}  [{}]",
+        env.display_name(),
+        env.dir.display(),
+        env.pid,
+        env.id
+    );
+
+    if let Some(description) = &env.description {
+        println!("    {description}");
+    }
+
+    let name_w = env.units.iter().map(|u| u.name.len()).max().unwrap_or(0);
+    let state_w = env
+        .units
+        .iter()
+        .map(|u| u.observed_state().to_string().len())
+        .max()
+        .unwrap_or(0);
+    let since_w = env
+        .units
+        .iter()
+        .filter_map(|u| u.since_string())
+        .map(|s| s.len())
+        .max()
+        .unwrap_or(0);
+    let has_any_port = env.units.iter().any(|u| env.port_for(&u.name).is_some());
+
+    for unit in &env.units {
+        let mut line = format!(
+            "    {} {:<name_w$}",
+            unit.style_state().display_indicator(),
+            unit.name
+        );
+        if has_any_port {
+            let port = env
+                .port_for(&unit.name)
+                .map(|p| format!(":{p}"))
+                .unwrap_or_default();
+            line.push_str(&format!("  {port:<6}"));
+        }
+        line.push_str(&format!(
+            "  {:<state_w$}",
+            unit.observed_state().to_string()
+        ));
+        let since = unit.since_string().unwrap_or_default();
+        line.push_str(&format!("  {since:>since_w$}"));
+        if let Some(detail) = &unit.detail {
+            line.push_str(&format!("  {detail}"));
+        }
+        println!("{}", line.trim_end());
+    }
+}
+
+/// `sutra history`'s filters.
+struct HistoryFilter {
+    env: Option<String>,
+    unit: Option<String>,
+    since: Option<Duration>,
+    state: Option<State>,
+}
+
+/// A journal entry with the time its unit spent in the previous state.
+#[derive(serde::Serialize)]
+struct HistoryEntry<'a> {
+    #[serde(flatten)]
+    entry: &'a journal::Entry,
+    /// `None` when the unit's previous transition isn't in the journal.
+    duration_secs: Option<u64>,
+}
+
+/// `sutra history`: filter the journal and print it oldest first.
+fn history(state_dir: &Path, filter: &HistoryFilter, json: bool) -> Result<(), String> {
+    let journal = Journal::for_state_dir(state_dir)
+        .ok_or("could not determine the journal path; set XDG_STATE_HOME")?;
+    let entries = journal.read();
+    // Durations come from the unfiltered journal, so `--state failed`
+    // still reports how long each unit was up before failing.
+    let durations = journal::durations(&entries);
+
+    let env_id = match &filter.env {
+        Some(target) => Some(
+            journal_env(&entries, target)
+                .ok_or_else(|| format!("no recorded environment matches {target:?}"))?,
+        ),
+        None => None,
+    };
+    let cutoff = filter
+        .since
+        .map(|d| model::now_secs().saturating_sub(d.as_secs()));
+
+    let selected: Vec<HistoryEntry> = entries
+        .iter()
+        .zip(durations)
+        .filter(|(e, _)| env_id.as_ref().is_none_or(|id| &e.env == id))
+        .filter(|(e, _)| filter.unit.as_ref().is_none_or(|u| &e.unit == u))
+        .filter(|(e, _)| cutoff.is_none_or(|c| e.ts >= c))
+        .filter(|(e, _)| filter.state.as_ref().is_none_or(|s| &e.new == s))
+        .map(|(entry, duration_secs)| HistoryEntry {
+            entry,
+            duration_secs,
+        })
+        .collect();
+
+    if json {
+        let out = serde_json::to_string_pretty(&selected).map_err(|e| e.to_string())?;
+        println!("{out}");
+        return Ok(());
+    }
+    if selected.is_empty() {
+        println!("No transitions recorded in {}", journal.path().display());
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 4]> = selected
+        .iter()
+        .map(|h| {
+            let e = h.entry;
+            let env = e
+                .dir
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| e.env.clone());
+            let old = e.old.as_ref().map_or("-".to_string(), State::to_string);
+            let took = h
+                .duration_secs
+                .map(|d| format!("after {}", model::format_duration(d)))
+                .unwrap_or_default();
+            [
+                format!("{env}/{}", e.unit),
+                format!("{old} \u{2192} {}", e.new),
+                took,
+                e.detail.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    let width = |col: usize| {
+        rows.iter()
+            .map(|r| r[col].chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let (unit_w, change_w, took_w) = (width(0), width(1), width(2));
+    for (h, row) in selected.iter().zip(&rows) {
+        let line = format!(
+            "{}  {:<unit_w$}  {:<change_w$}  {:<took_w$}  {}",
+            local_time(h.entry.ts),
+            row[0],
+            row[1],
+            row[2],
+            row[3]
+        );
+        println!("{}", line.trim_end());
+    }
+    Ok(())
+}
+
+/// One row of `sutra stats --json`.
+#[derive(serde::Serialize)]
+struct StatsRow<'a> {
+    dir: &'a Path,
+    unit: &'a str,
+    #[serde(flatten)]
+    summary: Summary,
+    /// `last / median` when the last run counts as a regression.
+    regression: Option<f64>,
+}
+
+/// `sutra stats`: duration summaries for every (dir, unit) in the journal.
+fn stats(state_dir: &Path, env: Option<&str>, json: bool) -> Result<(), String> {
+    let journal = Journal::for_state_dir(state_dir)
+        .ok_or("could not determine the journal path; set XDG_STATE_HOME")?;
+    let entries = journal.read();
+    let dir = match env {
+        Some(target) => {
+            let id = journal_env(&entries, target)
+                .ok_or_else(|| format!("no recorded environment matches {target:?}"))?;
+            entries.iter().find(|e| e.env == id).map(|e| e.dir.clone())
+        }
+        None => None,
+    };
+    let stats = Stats::from_entries(&entries);
+    let rows: Vec<StatsRow> = stats
+        .all()
+        .into_iter()
+        .filter(|(d, _, _)| dir.as_deref().is_none_or(|dir| *d == dir))
+        .map(|(dir, unit, summary)| StatsRow {
+            dir,
+            unit,
+            summary,
+            regression: summary.regression(),
+        })
+        .collect();
+
+    if json {
+        let out = serde_json::to_string_pretty(&rows).map_err(|e| e.to_string())?;
+        println!("{out}");
+        return Ok(());
+    }
+    if rows.is_empty() {
+        println!(
+            "No builds or startups recorded in {}",
+            journal.path().display()
+        );
+        return Ok(());
+    }
+
+    let names: Vec<String> = rows
+        .iter()
+        .map(|r| {
+            let env = r
+                .dir
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| r.dir.display().to_string());
+            format!("{env}/{}", r.unit)
+        })
+        .collect();
+    let name_w = names.iter().map(|n| n.chars().count()).max().unwrap_or(0);
+    println!(
+        "{:<name_w$}  {:<7}  {:>4}  {:>7}  {:>7}  {:>7}",
+        "UNIT", "PHASE", "RUNS", "MEDIAN", "P90", "LAST"
+    );
+    for (row, name) in rows.iter().zip(&names) {
+        let s = &row.summary;
+        let flag = row
+            .regression
+            .map(|ratio| format!("  {ratio:.1}\u{d7} slower than median"))
+            .unwrap_or_default();
+        println!(
+            "{name:<name_w$}  {:<7}  {:>4}  {:>7}  {:>7}  {:>7}{flag}",
+            s.phase.to_string(),
+            s.count,
+            model::format_duration(s.median),
+            model::format_duration(s.p90),
+            model::format_duration(s.last),
+        );
+    }
+    Ok(())
+}
+
+/// Resolve `--env` against the journal rather than the registry, since
+/// the environment may be long gone: an exact ID, or the deepest recorded
+/// `DIR` containing the given directory.
+fn journal_env(entries: &[journal::Entry], target: &str) -> Option<String> {
+    if entries.iter().any(|e| e.env == target) {
+        return Some(target.to_string());
+    }
+    let path = fs::canonicalize(target).ok()?;
+    entries
+        .iter()
+        .filter(|e| path.starts_with(&e.dir))
+        .max_by_key(|e| e.dir.components().count())
+        .map(|e| e.env.clone())
+}
+
+/// Format epoch seconds as local `YYYY-MM-DD HH:MM:SS`.
+fn local_time(ts: u64) -> String {
+    let t = ts as nix::libc::time_t;
+    let mut tm: nix::libc::tm = unsafe { std::mem::zeroed() };
+    // SAFETY: both pointers are valid for the duration of the call, and
+    // localtime_r is the reentrant variant.
+    if unsafe { nix::libc::localtime_r(&t, &mut tm) }.is_null() {
+        return ts.to_string();
+    }
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+/// Resolve an `<id|dir>` argument: an exact environment ID, or else a
+/// directory inside a registered project.
+fn select_env<'a>(envs: &'a [Environment], target: &str) -> Option<&'a Environment> {
+    if let Some(env) = envs.iter().find(|e| e.id == target) {
+        return Some(env);
+    }
+    let path = Path::new(target);
+    if path.exists() {
+        return model::find_for_dir(envs, path);
+    }
+    None
+}
+
+/// `sutra wait`: re-evaluate the conditions on every registry event until
+/// they all hold, a unit fails, the environment dies, or time runs out.
+/// Returns the exit code: 0, [`EXIT_WAIT_FAILED`] or [`EXIT_WAIT_TIMEOUT`].
+///
+/// An environment that isn't registered yet, or only has a dead
+/// registration left over from an earlier run, is waited for, so a script
+/// can start its dev runner in the background and wait immediately. Only
+/// an environment seen alive during this wait dying counts as a failure.
+fn wait(
+    state_dir: &Path,
+    target: Option<&str>,
+    conditions: &[(String, State)],
+    timeout: Option<Duration>,
+) -> Result<i32, String> {
+    let target = match target {
+        Some(t) => t.to_string(),
+        None => std::env::current_dir()
+            .map_err(|e| format!("current directory: {e}"))?
+            .display()
+            .to_string(),
+    };
+    // Subscribe before the first load so no transition slips between them.
+    let watcher = RegistryWatcher::new(state_dir).map_err(|e| e.to_string())?;
+    let deadline = timeout.map(|t| Instant::now() + t);
+    // (ID, PID) of the registration last seen alive.
+    let mut seen_alive: Option<(String, u32)> = None;
+
+    loop {
+        let envs = model::load_all(state_dir);
+        let mut pending: Vec<String> = Vec::new();
+
+        match select_env(&envs, &target) {
+            None => pending.push(format!("environment {target:?} (not registered)")),
+            Some(env) if !env.alive => {
+                if seen_alive.as_ref() == Some(&(env.id.clone(), env.pid)) {
+                    eprintln!(
+                        "sutra: environment {} (pid {}) is not running",
+                        env.id, env.pid
+                    );
+                    return Ok(EXIT_WAIT_FAILED);
                 }
+                pending.push(format!("environment {target:?} (not running)"));
+            }
+            Some(env) => {
+                seen_alive = Some((env.id.clone(), env.pid));
+                for (unit_name, want) in conditions {
+                    let unit = env.units.iter().find(|u| &u.name == unit_name);
+                    let state = unit.map(|u| &u.state).unwrap_or(&State::None);
+                    if let Some(pid) = unit.filter(|u| u.dead).and_then(|u| u.pid) {
+                        eprintln!("sutra: {unit_name} is {state} but pid {pid} is gone");
+                        return Ok(EXIT_WAIT_FAILED);
+                    }
+                    if state == want {
+                        continue;
+                    }
+                    if *state == State::Failed {
+                        let detail = unit
+                            .and_then(|u| u.detail.as_deref())
+                            .map(|d| format!(": {d}"))
+                            .unwrap_or_default();
+                        eprintln!("sutra: {unit_name} failed{detail}");
+                        return Ok(EXIT_WAIT_FAILED);
+                    }
+                    let now = match unit {
+                        Some(_) => unit_label(state),
+                        None => "not published".into(),
+                    };
+                    pending.push(format!("{unit_name}={want} (is {now})"));
+                }
+            }
+        }
+
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        let mut slice = WAIT_LIVENESS_INTERVAL;
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                eprintln!("sutra: timed out waiting for {}", pending.join(", "));
+                return Ok(EXIT_WAIT_TIMEOUT);
             }
+            slice = slice.min(remaining);
+        }
+        match watcher.rx.recv_timeout(slice) {
+            Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Err("registry watcher stopped".into()),
         }
     }
 }
 
+/// `sutra run` / `sutra tap`: supervise one command as `unit` and exit
+/// with its code. With `rules`, its output is matched to infer state.
+///
+/// Nested runs (started from inside another `sutra run`/`up`, which
+/// export `SUTRA_ENV_ID`) join the parent's environment instead of
+/// registering their own, so they show up as sibling units.
+fn run(
+    state_dir: &Path,
+    unit: &str,
+    rules: Vec<Rule>,
+    ports: Vec<(String, u16)>,
+    dir: &Path,
+    command: Vec<String>,
+) -> Result<(), String> {
+    writer::validate_unit_name(unit).map_err(|e| e.to_string())?;
+    let dir = fs::canonicalize(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    let id = match std::env::var(supervisor::ENV_ID_VAR) {
+        Ok(id) if !id.is_empty() => {
+            if !model::is_meta_file(&id) {
+                return Err(format!(
+                    "invalid environment id {id:?} in ${}",
+                    supervisor::ENV_ID_VAR
+                ));
+            }
+            id
+        }
+        _ => model::env_id_for(&dir).map_err(|e| format!("{}: {e}", dir.display()))?,
+    };
+
+    let mut meta = Meta::new(dir, std::process::id());
+    meta.ports = ports.into_iter().collect();
+    let mut reg =
+        Registration::attach(state_dir, &id, meta).map_err(|e| format!("registering {id}: {e}"))?;
+
+    let program = command[0].clone();
+    let mut spec = UnitSpec::new(unit, command);
+    spec.rules = rules;
+    let code = supervisor::run(&mut reg, spec).map_err(|e| format!("{program}: {e}"))?;
+    drop(reg);
+    std::process::exit(code);
+}
+
+/// `sutra up`: register the project and supervise its configured units
+/// until they all exit or we're told to stop.
+fn up(state_dir: &Path, only: &[String], file: Option<PathBuf>) -> Result<(), String> {
+    let file = match file {
+        Some(file) => file,
+        None => {
+            let cwd = std::env::current_dir().map_err(|e| format!("current directory: {e}"))?;
+            sutra::config::discover(&cwd).ok_or_else(|| {
+                format!(
+                    "no {} in {}",
+                    sutra::config::CONFIG_FILES.join(" or "),
+                    cwd.display()
+                )
+            })?
+        }
+    };
+    let config = sutra::config::load(&file).map_err(|e| e.to_string())?;
+    if let Some(unknown) = only
+        .iter()
+        .find(|name| !config.units.iter().any(|u| &u.name == *name))
+    {
+        return Err(format!("no unit {unknown:?} in {}", file.display()));
+    }
+    let units: Vec<_> = config
+        .units
+        .into_iter()
+        .filter(|u| only.is_empty() || only.contains(&u.name))
+        .collect();
+
+    let id =
+        model::env_id_for(&config.dir).map_err(|e| format!("{}: {e}", config.dir.display()))?;
+    let mut meta = Meta::new(config.dir, std::process::id());
+    meta.ports = units
+        .iter()
+        .filter_map(|u| u.port.map(|p| (u.name.clone(), p)))
+        .collect();
+    let mut reg =
+        Registration::attach(state_dir, &id, meta).map_err(|e| format!("registering {id}: {e}"))?;
+
+    let specs = units
+        .into_iter()
+        .map(|u| {
+            let mut spec = UnitSpec::shell(&u.name, &u.command);
+            spec.dir = Some(u.dir);
+            // Procfile convention: tell the process which port to bind.
+            if let Some(port) = u.port {
+                spec.env.push(("PORT".into(), port.to_string()));
+            }
+            spec.ready = u.ready;
+            spec.rules = u.rules;
+            spec
+        })
+        .collect();
+    let code = supervisor::run_all(&mut reg, specs).map_err(|e| e.to_string())?;
+    drop(reg);
+    std::process::exit(code);
+}
+
+fn unit_label(state: &State) -> String {
+    match state {
+        State::None => "empty".into(),
+        s => s.to_string(),
+    }
+}
+
+/// Parse a `UNIT=STATE` wait condition.
+fn parse_condition_arg(s: &str) -> Result<(String, State), String> {
+    let (unit, state) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected UNIT=STATE, got {s:?}"))?;
+    writer::validate_unit_name(unit).map_err(|e| e.to_string())?;
+    Ok((unit.to_string(), State::from_user_str(state)?))
+}
+
+fn parse_progress_arg(s: &str) -> Result<Progress, String> {
+    Progress::parse(s).ok_or_else(|| format!("expected CURRENT/TOTAL or PERCENT%, got {s:?}"))
+}
+
+/// Parse a `--port UNIT=PORT` argument.
+fn parse_port_arg(s: &str) -> Result<(String, u16), String> {
+    let (unit, port) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected UNIT=PORT, got {s:?}"))?;
+    let unit = unit.trim().to_lowercase();
+    writer::validate_unit_name(&unit).map_err(|e| e.to_string())?;
+    let port = port
+        .trim()
+        .parse::<u16>()
+        .map_err(|_| format!("invalid port {port:?}"))?;
+    Ok((unit, port))
+}
+
 /// Fork the process and exit the parent so the GUI runs detached from the terminal.
 fn daemonize() {
     use std::env;
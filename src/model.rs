@@ -1,10 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use nix::sys::signal;
-use nix::unistd::Pid;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::liveness::{self, Liveness};
 
 /// State of a unit
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -32,7 +35,22 @@ impl State {
         }
     }
 
-    pub fn display_indicator(&self) -> &str {
+    /// Parse a state typed by the user (a CLI argument or pattern rule).
+    /// Only the keywords in [`USER_STATES`] are accepted; a typo like
+    /// `Ready` would otherwise become `State::Other` and silently lose its
+    /// color and sound.
+    pub fn from_user_str(s: &str) -> Result<State, String> {
+        if USER_STATES.contains(&s) {
+            Ok(State::parse(s))
+        } else {
+            Err(format!(
+                "unknown state {s:?}; expected one of: {}",
+                USER_STATES.join(", ")
+            ))
+        }
+    }
+
+    pub fn display_indicator(&self) -> &'static str {
         match self {
             State::None => "\u{25cb}",     // ○
             State::Starting => "\u{25cc}", // ◌
@@ -51,8 +69,28 @@ impl State {
             State::Starting | State::Building | State::Running | State::Ready
         )
     }
+
+    /// What sutra shows for a unit that claims an active state while its
+    /// process is gone (see [`UnitStatus::dead`]). sutra never writes it
+    /// to a status file itself; stored as `Other` so it round-trips
+    /// through the journal.
+    pub fn dead() -> State {
+        State::Other(DEAD.to_string())
+    }
+
+    pub fn is_dead(&self) -> bool {
+        matches!(self, State::Other(s) if s == DEAD)
+    }
 }
 
+const DEAD: &str = "dead";
+
+/// State keywords users may pass to `sutra set`, `--state` filters and pattern
+/// rules.
+pub const USER_STATES: [&str; 7] = [
+    "starting", "building", "running", "ready", "failed", "stopped", DEAD,
+];
+
 impl fmt::Display for State {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let label = match self {
@@ -69,15 +107,120 @@ impl fmt::Display for State {
     }
 }
 
+/// Serialized as the status-file keyword (`"ready"`, `"building"`, …);
+/// `State::None` is the empty string.
+impl Serialize for State {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for State {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(if s.is_empty() {
+            State::None
+        } else {
+            State::parse(&s)
+        })
+    }
+}
+
+/// How far along a unit is, e.g. `42/120` or `42%` (stored as `42/100`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progress {
+    pub current: u64,
+    pub total: u64,
+}
+
+impl Progress {
+    /// Parse `<current>/<total>` or `<percent>%`. A zero total is
+    /// rejected; `current` past `total` is clamped by [`Progress::fraction`].
+    pub fn parse(s: &str) -> Option<Progress> {
+        let s = s.trim();
+        if let Some(pct) = s.strip_suffix('%') {
+            let current = pct.trim().parse().ok()?;
+            return Some(Progress {
+                current,
+                total: 100,
+            });
+        }
+        let (current, total) = s.split_once('/')?;
+        let current = current.trim().parse().ok()?;
+        let total = total.trim().parse().ok().filter(|t| *t > 0)?;
+        Some(Progress { current, total })
+    }
+
+    /// Completion in `0.0..=1.0`.
+    pub fn fraction(&self) -> f32 {
+        (self.current.min(self.total) as f64 / self.total as f64) as f32
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.total == 100 {
+            write!(f, "{}%", self.current)
+        } else {
+            write!(f, "{}/{}", self.current, self.total)
+        }
+    }
+}
+
 /// Status of a single named unit (e.g., "server", "vite")
-#[derive(Debug, Clone)]
+///
+/// The optional fields come from the `KEY=VALUE` lines of a v2 status
+/// file; see [`UnitStatus::parse`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnitStatus {
     pub name: String,
     pub state: State,
     pub detail: Option<String>,
+    /// `PROGRESS=42/120` or `PROGRESS=42%`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<Progress>,
+    /// `SINCE=<epoch seconds>`: when the unit entered this state.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+    /// `LOG=<path>`: where the unit's output goes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log: Option<PathBuf>,
+    /// `ERRORS=<n>`: error count, e.g. from the last build.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errors: Option<u32>,
+    /// `PID=<pid>`: the process behind this unit. [`Environment::load`]
+    /// falls back to the meta file's `<UNIT>_PID`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    /// When the unit entered its current state, in epoch seconds: `SINCE`
+    /// if the writer gave one, else the status file's mtime. Kept steady
+    /// across rewrites that don't change the state by [`SinceTracker`].
+    /// Not part of the file format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_since: Option<u64>,
+    /// The unit claims an active state but its `pid` is gone, e.g. a
+    /// child that crashed before it could write `failed`. Not part of the
+    /// file format.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dead: bool,
 }
 
 impl UnitStatus {
+    pub fn new(name: &str, state: State, detail: Option<&str>) -> UnitStatus {
+        UnitStatus {
+            name: name.to_string(),
+            state,
+            detail: detail.map(str::to_string),
+            progress: None,
+            since: None,
+            log: None,
+            errors: None,
+            pid: None,
+            state_since: None,
+            dead: false,
+        }
+    }
+
     /// Parse a status value like "building: Compiling Rust bindings".
     /// The `name` comes from the status filename, not the content.
     /// Uses State::None for empty content.
@@ -87,17 +230,32 @@ impl UnitStatus {
     /// same way — accepting both is friendlier to dev-script authors
     /// who forget the space and would otherwise see their state fall
     /// through to `State::Other`.
+    ///
+    /// v2 files follow the state line with `KEY=VALUE` lines for the keys
+    /// in [`FIELD_KEYS`]. Other trailing lines, including `KEY=VALUE`
+    /// lines with any other key (an env dump, say), are treated as a
+    /// continuation of the detail, exactly as v1 parsing did.
     pub fn parse(name: &str, content: &str) -> UnitStatus {
+        let mut status = UnitStatus::new(name, State::None, None);
         let trimmed = content.trim();
         if trimmed.is_empty() {
-            return UnitStatus {
-                name: name.to_string(),
-                state: State::None,
-                detail: None,
-            };
+            return status;
+        }
+
+        let mut head = String::new();
+        for line in trimmed.lines() {
+            if !head.is_empty() {
+                if let Some((key, value)) = field_line(line) {
+                    status.set_field(key, value);
+                    continue;
+                }
+                head.push('\n');
+            }
+            head.push_str(line);
         }
+        let head = head.trim();
 
-        let (state_str, detail) = match trimmed.split_once(':') {
+        let (state_str, detail) = match head.split_once(':') {
             Some((s, d)) => {
                 let detail = d.trim();
                 let detail = if detail.is_empty() {
@@ -107,43 +265,295 @@ impl UnitStatus {
                 };
                 (s.trim_end(), detail)
             }
-            None => (trimmed, None),
+            None => (head, None),
         };
 
-        let state = State::parse(state_str);
-        UnitStatus {
-            name: name.to_string(),
-            state,
-            detail,
+        status.state = State::parse(state_str);
+        status.detail = detail;
+        status
+    }
+
+    /// Progress to display: the `PROGRESS` field, or else — while the
+    /// unit is `starting` or `building` — the detail's first word if it
+    /// reads as `42%` or `12/120`, so v1 writers get a progress bar too.
+    pub fn display_progress(&self) -> Option<Progress> {
+        if self.progress.is_some() {
+            return self.progress;
+        }
+        if !matches!(self.state, State::Starting | State::Building) {
+            return None;
+        }
+        self.detail
+            .as_deref()?
+            .split_whitespace()
+            .next()
+            .and_then(|token| Progress::parse(token.trim_matches(|c: char| "()[],;:".contains(c))))
+    }
+
+    /// The state to show and to notify on: [`State::dead`] for a
+    /// [`dead`](UnitStatus::dead) unit, else `state`.
+    pub fn observed_state(&self) -> State {
+        if self.dead {
+            State::dead()
+        } else {
+            self.state.clone()
+        }
+    }
+
+    /// The state whose indicator and color to use: `failed` for a dead
+    /// unit, else `state`.
+    pub fn style_state(&self) -> &State {
+        if self.dead {
+            &State::Failed
+        } else {
+            &self.state
+        }
+    }
+
+    /// Time in the current state as a compact string (`3m`), if known.
+    pub fn since_string(&self) -> Option<String> {
+        self.state_since
+            .map(|since| format_duration(now_secs().saturating_sub(since)))
+    }
+
+    /// Store one v2 `KEY=VALUE` field. Unparsable values are dropped.
+    fn set_field(&mut self, key: &str, value: &str) {
+        let value = value.trim();
+        match key {
+            "PROGRESS" => self.progress = Progress::parse(value),
+            "SINCE" => self.since = value.parse().ok(),
+            "LOG" => self.log = (!value.is_empty()).then(|| PathBuf::from(value)),
+            "ERRORS" => self.errors = value.parse().ok(),
+            "PID" => self.pid = value.parse().ok(),
+            _ => {}
         }
     }
 }
 
-/// A registered environment instance
-#[derive(Debug, Clone)]
-pub struct Environment {
-    pub id: String,
+/// The keys a v2 status file may set. A `KEY=VALUE` line with any other
+/// key is detail text, so v1 details keep their `FOO=bar` lines.
+pub const FIELD_KEYS: [&str; 5] = ["PROGRESS", "SINCE", "LOG", "ERRORS", "PID"];
+
+/// Split a v2 field line, `KEY=VALUE` with a key from [`FIELD_KEYS`].
+fn field_line(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once('=')?;
+    FIELD_KEYS.contains(&key).then_some((key, value))
+}
+
+/// Renders the status file content, `<state>[: <detail>]`, in the
+/// canonical form that `UnitStatus::parse` reads back. Line breaks in the
+/// detail are flattened to spaces since the state line is a single line.
+/// Set v2 fields follow as `KEY=VALUE` lines; with none set, the output
+/// is a plain v1 status.
+impl fmt::Display for UnitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.state)?;
+        if let Some(detail) = self.detail.as_deref().filter(|d| !d.trim().is_empty()) {
+            write!(f, ": {}", detail.trim().replace(['\n', '\r'], " "))?;
+        }
+        let flat = |v: &str| v.replace(['\n', '\r'], " ");
+        if let Some(progress) = &self.progress {
+            write!(f, "\nPROGRESS={progress}")?;
+        }
+        if let Some(since) = self.since {
+            write!(f, "\nSINCE={since}")?;
+        }
+        if let Some(log) = &self.log {
+            write!(f, "\nLOG={}", flat(&log.display().to_string()))?;
+        }
+        if let Some(errors) = self.errors {
+            write!(f, "\nERRORS={errors}")?;
+        }
+        if let Some(pid) = self.pid {
+            write!(f, "\nPID={pid}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A clickable link for a unit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link {
+    /// `None` for the unit's default link (`<UNIT>_URL` or its port),
+    /// otherwise the lowercase `<LABEL>` from `<UNIT>_URL_<LABEL>`.
+    pub label: Option<String>,
+    pub url: String,
+}
+
+/// How often a health check runs when `<UNIT>_HEALTH_INTERVAL` isn't set.
+pub const DEFAULT_HEALTH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a health check may take when `<UNIT>_HEALTH_TIMEOUT` isn't set.
+pub const DEFAULT_HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// An HTTP health check for a unit, from `<UNIT>_HEALTH=<http:// URL>`
+/// with optional `<UNIT>_HEALTH_INTERVAL` and `<UNIT>_HEALTH_TIMEOUT`
+/// durations (`5s`, `500ms`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<Duration>,
+}
+
+impl HealthCheck {
+    pub fn interval(&self) -> Duration {
+        self.interval.unwrap_or(DEFAULT_HEALTH_INTERVAL)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout.unwrap_or(DEFAULT_HEALTH_TIMEOUT)
+    }
+}
+
+/// Result of the latest run of a unit's [`HealthCheck`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Health {
+    /// HTTP status, or `None` if the request failed or timed out.
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    /// Any check of this unit has passed since it was declared, so a
+    /// failure now means it went down rather than hasn't come up yet.
+    pub has_passed: bool,
+}
+
+impl Health {
+    /// A 2xx or 3xx answer; see [`probe::status_ok`](crate::probe::status_ok).
+    pub fn ok(&self) -> bool {
+        self.status.is_some_and(crate::probe::status_ok)
+    }
+
+    /// `healthy 12ms`, `HTTP 503 12ms` or `unreachable`.
+    pub fn label(&self) -> String {
+        match self.status {
+            _ if self.ok() => format!("healthy {}ms", self.latency_ms),
+            Some(status) => format!("HTTP {status} {}ms", self.latency_ms),
+            None => "unreachable".to_string(),
+        }
+    }
+
+    /// The state shown for a unit whose writer publishes none: `ready`
+    /// while healthy, `starting` until the first pass, `failed` after.
+    pub fn synthetic_state(&self) -> State {
+        if self.ok() {
+            State::Ready
+        } else if self.has_passed {
+            State::Failed
+        } else {
+            State::Starting
+        }
+    }
+}
+
+/// Who is actually listening on a conflicting port.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", tag = "kind")]
+pub enum PortOwner {
+    /// A process started by another registered environment.
+    Environment { label: String, pid: u32 },
+    /// A process sutra can't tie to any environment.
+    Process { pid: u32, name: Option<String> },
+}
+
+impl fmt::Display for PortOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortOwner::Environment { label, pid } => write!(f, "{label} (pid {pid})"),
+            PortOwner::Process {
+                pid,
+                name: Some(name),
+            } => write!(f, "pid {pid} ({name})"),
+            PortOwner::Process { pid, name: None } => write!(f, "pid {pid}"),
+        }
+    }
+}
+
+/// A unit's declared port is also declared by another live environment,
+/// or is bound by a process outside this environment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortConflict {
+    pub port: u16,
+    /// Labels of the other live environments declaring the same port.
+    pub declared_by: Vec<String>,
+    /// Whoever has the port bound, if not this environment. `None` if
+    /// nothing else is bound, or the owner can't be seen.
+    pub owner: Option<PortOwner>,
+}
+
+impl PortConflict {
+    /// `:3000 also declared by client-b/app; bound by client-b/app (pid 4242)`
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.declared_by.is_empty() {
+            parts.push(format!("also declared by {}", self.declared_by.join(", ")));
+        }
+        if let Some(owner) = &self.owner {
+            parts.push(format!("bound by {owner}"));
+        }
+        format!(":{} {}", self.port, parts.join("; "))
+    }
+}
+
+/// The `KEY=VALUE` contents of an environment meta file.
+///
+/// `Environment::load` parses meta files through this type and writers
+/// render them through it, so both sides share one definition of the keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Meta {
     pub dir: PathBuf,
     pub pid: u32,
-    pub ports: HashMap<String, u16>, // lowercase unit name → port
-    pub started: u64,
-    pub alive: bool,
-    pub units: Vec<UnitStatus>,
+    pub started: Option<u64>,
+    pub ports: BTreeMap<String, u16>, // lowercase unit name → port
+    /// Lowercase unit name → PID, from `<UNIT>_PID` keys.
+    pub pids: BTreeMap<String, u32>,
+    /// Lowercase unit name → health check, from `<UNIT>_HEALTH` keys.
+    pub health_checks: BTreeMap<String, HealthCheck>,
+    /// Lowercase unit name → link label (`""` for the default link) → URL
+    /// or `/path`, from `<UNIT>_URL` and `<UNIT>_URL_<LABEL>` keys.
+    pub urls: BTreeMap<String, BTreeMap<String, String>>,
+    /// Display name, overriding the `DIR` basename.
+    pub name: Option<String>,
+    /// One-line description shown under the name.
+    pub description: Option<String>,
+    /// Emoji or short glyph shown before the name.
+    pub icon: Option<String>,
 }
 
-impl Environment {
-    /// Load an environment instance from its meta file.
-    ///
-    /// Meta file: `~/.dev-runner/<hash>` (KEY=VALUE lines)
-    /// Status files: `~/.dev-runner/<hash>.<unit_name>.status` (single line: `<state>[: <detail>]`)
-    pub fn load(meta_path: &Path) -> Option<Environment> {
-        let content = fs::read_to_string(meta_path).ok()?;
-        let id = meta_path.file_name()?.to_str()?.to_string();
+impl Meta {
+    pub fn new(dir: PathBuf, pid: u32) -> Meta {
+        Meta {
+            dir,
+            pid,
+            started: None,
+            ports: BTreeMap::new(),
+            pids: BTreeMap::new(),
+            health_checks: BTreeMap::new(),
+            urls: BTreeMap::new(),
+            name: None,
+            description: None,
+            icon: None,
+        }
+    }
 
+    /// Parse meta file content. Returns `None` if a required key (`DIR`,
+    /// `PID`) is missing or malformed. Unrecognized keys are ignored.
+    pub fn parse(content: &str) -> Option<Meta> {
         let mut dir = None;
         let mut pid = None;
-        let mut ports = HashMap::new();
+        let mut ports = BTreeMap::new();
+        let mut pids = BTreeMap::new();
+        let mut health_urls = BTreeMap::new();
+        let mut health_intervals = BTreeMap::new();
+        let mut health_timeouts = BTreeMap::new();
+        let mut urls: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
         let mut started = None;
+        let mut name = None;
+        let mut description = None;
+        let mut icon = None;
+        // Free-text keys: blank values count as unset.
+        let text = |value: &str| Some(value.trim().to_string()).filter(|v| !v.is_empty());
 
         for line in content.lines() {
             let Some((key, value)) = line.split_once('=') else {
@@ -153,21 +563,252 @@ impl Environment {
                 "DIR" => dir = Some(PathBuf::from(value)),
                 "PID" => pid = value.parse().ok(),
                 "STARTED" => started = value.parse().ok(),
+                "NAME" => name = text(value),
+                "DESCRIPTION" => description = text(value),
+                "ICON" => icon = text(value),
                 k if k.ends_with("_PORT") => {
                     if let Ok(port) = value.parse::<u16>() {
                         let name = k.strip_suffix("_PORT").unwrap().to_lowercase();
                         ports.insert(name, port);
                     }
                 }
-                _ => {}
+                k if k.len() > "_PID".len() && k.ends_with("_PID") => {
+                    if let Ok(pid) = value.parse::<u32>() {
+                        let name = k.strip_suffix("_PID").unwrap().to_lowercase();
+                        pids.insert(name, pid);
+                    }
+                }
+                k if k.len() > "_HEALTH".len() && k.ends_with("_HEALTH") => {
+                    let value = value.trim();
+                    if value.starts_with("http://") {
+                        let name = k.strip_suffix("_HEALTH").unwrap().to_lowercase();
+                        health_urls.insert(name, value.to_string());
+                    }
+                }
+                k if k.len() > "_HEALTH_INTERVAL".len() && k.ends_with("_HEALTH_INTERVAL") => {
+                    if let Ok(d) = humantime::parse_duration(value.trim()) {
+                        let name = k.strip_suffix("_HEALTH_INTERVAL").unwrap().to_lowercase();
+                        health_intervals.insert(name, d);
+                    }
+                }
+                k if k.len() > "_HEALTH_TIMEOUT".len() && k.ends_with("_HEALTH_TIMEOUT") => {
+                    if let Ok(d) = humantime::parse_duration(value.trim()) {
+                        let name = k.strip_suffix("_HEALTH_TIMEOUT").unwrap().to_lowercase();
+                        health_timeouts.insert(name, d);
+                    }
+                }
+                k => {
+                    let Some((unit, label)) = parse_url_key(k) else {
+                        continue;
+                    };
+                    let value = value.trim();
+                    if value.contains("://") || value.starts_with('/') {
+                        urls.entry(unit)
+                            .or_default()
+                            .insert(label, value.to_string());
+                    }
+                }
             }
         }
 
-        let pid = pid?;
-        let alive = match i32::try_from(pid) {
-            Ok(raw_pid) => signal::kill(Pid::from_raw(raw_pid), None).is_ok(),
-            Err(_) => false,
-        };
+        // Interval and timeout keys only count for units with a URL.
+        let health_checks = health_urls
+            .into_iter()
+            .map(|(unit, url)| {
+                let check = HealthCheck {
+                    url,
+                    interval: health_intervals.get(&unit).copied(),
+                    timeout: health_timeouts.get(&unit).copied(),
+                };
+                (unit, check)
+            })
+            .collect();
+
+        Some(Meta {
+            dir: dir?,
+            pid: pid?,
+            started,
+            ports,
+            pids,
+            health_checks,
+            urls,
+            name,
+            description,
+            icon,
+        })
+    }
+
+    /// Resolve each unit's links: the default link first (`<UNIT>_URL`,
+    /// else `http://localhost:<port>`), then named links by label.
+    /// Path-only values are joined onto the unit's localhost port and
+    /// dropped if it has none.
+    pub fn links(&self) -> BTreeMap<String, Vec<Link>> {
+        let mut out = BTreeMap::new();
+        let units = self.ports.keys().chain(self.urls.keys());
+        for unit in units {
+            if out.contains_key(unit) {
+                continue;
+            }
+            let port = self.ports.get(unit).copied();
+            let resolve = |url: &str| match url.strip_prefix('/') {
+                Some(path) => port.map(|p| format!("http://localhost:{p}/{path}")),
+                None => Some(url.to_string()),
+            };
+            let named = self.urls.get(unit);
+            let default = named
+                .and_then(|m| m.get(""))
+                .and_then(|u| resolve(u))
+                .or_else(|| port.map(|p| format!("http://localhost:{p}")));
+
+            let mut links: Vec<Link> = default
+                .map(|url| Link { label: None, url })
+                .into_iter()
+                .collect();
+            for (label, url) in named.into_iter().flatten() {
+                if label.is_empty() {
+                    continue;
+                }
+                if let Some(url) = resolve(url) {
+                    links.push(Link {
+                        label: Some(label.clone()),
+                        url,
+                    });
+                }
+            }
+            if !links.is_empty() {
+                out.insert(unit.clone(), links);
+            }
+        }
+        out
+    }
+}
+
+/// Split a `<UNIT>_URL` or `<UNIT>_URL_<LABEL>` key into the lowercase
+/// unit name and label (`""` for the default link).
+fn parse_url_key(key: &str) -> Option<(String, String)> {
+    if let Some(unit) = key.strip_suffix("_URL") {
+        return (!unit.is_empty()).then(|| (unit.to_lowercase(), String::new()));
+    }
+    let (unit, label) = key.rsplit_once("_URL_")?;
+    (!unit.is_empty() && !label.is_empty()).then(|| (unit.to_lowercase(), label.to_lowercase()))
+}
+
+/// Renders the meta file content. Port keys are written as
+/// `<UNIT>_PORT`, the uppercase form of the unit name.
+impl fmt::Display for Meta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "DIR={}", self.dir.display())?;
+        writeln!(f, "PID={}", self.pid)?;
+        if let Some(started) = self.started {
+            writeln!(f, "STARTED={}", started)?;
+        }
+        for (name, port) in &self.ports {
+            writeln!(f, "{}_PORT={}", name.to_uppercase(), port)?;
+        }
+        for (name, pid) in &self.pids {
+            writeln!(f, "{}_PID={}", name.to_uppercase(), pid)?;
+        }
+        for (name, check) in &self.health_checks {
+            let name = name.to_uppercase();
+            writeln!(f, "{name}_HEALTH={}", check.url)?;
+            if let Some(interval) = check.interval {
+                writeln!(
+                    f,
+                    "{name}_HEALTH_INTERVAL={}",
+                    humantime::format_duration(interval)
+                )?;
+            }
+            if let Some(timeout) = check.timeout {
+                writeln!(
+                    f,
+                    "{name}_HEALTH_TIMEOUT={}",
+                    humantime::format_duration(timeout)
+                )?;
+            }
+        }
+        for (unit, urls) in &self.urls {
+            for (label, url) in urls {
+                match label.as_str() {
+                    "" => writeln!(f, "{}_URL={}", unit.to_uppercase(), url)?,
+                    label => writeln!(
+                        f,
+                        "{}_URL_{}={}",
+                        unit.to_uppercase(),
+                        label.to_uppercase(),
+                        url
+                    )?,
+                }
+            }
+        }
+        for (key, value) in [
+            ("NAME", &self.name),
+            ("DESCRIPTION", &self.description),
+            ("ICON", &self.icon),
+        ] {
+            if let Some(value) = value {
+                writeln!(f, "{key}={}", value.replace(['\n', '\r'], " "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A registered environment instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Environment {
+    pub id: String,
+    pub dir: PathBuf,
+    pub pid: u32,
+    pub ports: HashMap<String, u16>, // lowercase unit name → port
+    /// Lowercase unit name → links, default first (see [`Meta::links`]).
+    pub links: HashMap<String, Vec<Link>>,
+    pub started: u64,
+    pub alive: bool,
+    /// `PID` exists but started after `STARTED`, so it's an unrelated
+    /// process that reused the number. Never `alive`.
+    pub stale_pid: bool,
+    pub units: Vec<UnitStatus>,
+    /// Lowercase unit name → whether its port accepts connections, as
+    /// last seen by a [`PortProber`](crate::probe::PortProber). Empty
+    /// unless one was applied.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub listening: HashMap<String, bool>,
+    /// Lowercase unit name → conflict over its port, found by a
+    /// [`PortProber`](crate::probe::PortProber).
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub port_conflicts: HashMap<String, PortConflict>,
+    /// Lowercase unit name → HTTP health check, from `<UNIT>_HEALTH`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub health_checks: HashMap<String, HealthCheck>,
+    /// Lowercase unit name → latest health check result, filled in by a
+    /// [`HealthProber`](crate::probe::HealthProber). Empty unless one was
+    /// applied.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub health: HashMap<String, Health>,
+    /// `NAME` from the meta file.
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    /// What the UIs call this environment: `NAME`, else a `DIR` suffix
+    /// long enough to tell it apart from the others (see [`load_all`]).
+    pub label: String,
+}
+
+impl Environment {
+    /// Load an environment instance from its meta file.
+    ///
+    /// Meta file: `<state_dir>/<hash>` (KEY=VALUE lines)
+    /// Status files: `<state_dir>/<hash>.<unit_name>.status` (single line: `<state>[: <detail>]`)
+    ///
+    /// Status files are looked up next to `meta_path`, so whichever state
+    /// directory the meta file lives in is the one that gets scanned.
+    pub fn load(meta_path: &Path) -> Option<Environment> {
+        let content = fs::read_to_string(meta_path).ok()?;
+        let id = meta_path.file_name()?.to_str()?.to_string();
+        let meta = Meta::parse(&content)?;
+
+        let pid = meta.pid;
+        let liveness = liveness::check(pid, meta.started);
 
         // Scan for status files in both conventions:
         //   new: <hash>.<unit_name>.status
@@ -188,7 +829,16 @@ impl Environment {
                     if let Some(unit_name) = rest.strip_suffix(".status") {
                         if !unit_name.is_empty() {
                             if let Ok(status_content) = fs::read_to_string(entry.path()) {
-                                units.push(UnitStatus::parse(unit_name, &status_content));
+                                let mut unit = UnitStatus::parse(unit_name, &status_content);
+                                unit.state_since = unit.since.or_else(|| modified_secs(&entry));
+                                unit.pid = unit.pid.or_else(|| meta.pids.get(unit_name).copied());
+                                // A unit's process starts after STARTED by
+                                // design, so only existence is checked.
+                                unit.dead = unit.state.is_active()
+                                    && unit.pid.is_some_and(|pid| {
+                                        liveness::check(pid, None) != Liveness::Alive
+                                    });
+                                units.push(unit);
                             }
                         }
                     }
@@ -198,14 +848,29 @@ impl Environment {
 
         units.sort_by(|a, b| a.name.cmp(&b.name));
 
+        let label = meta
+            .name
+            .clone()
+            .unwrap_or_else(|| path_suffix(&meta.dir, 1));
+        let links = meta.links().into_iter().collect();
         Some(Environment {
             id,
-            dir: dir?,
+            dir: meta.dir,
             pid,
-            ports,
-            started: started.unwrap_or(0),
-            alive,
+            ports: meta.ports.into_iter().collect(),
+            links,
+            started: meta.started.unwrap_or(0),
+            alive: liveness == Liveness::Alive,
+            stale_pid: liveness == Liveness::StalePid,
             units,
+            listening: HashMap::new(),
+            port_conflicts: HashMap::new(),
+            health_checks: meta.health_checks.into_iter().collect(),
+            health: HashMap::new(),
+            name: meta.name,
+            description: meta.description,
+            icon: meta.icon,
+            label,
         })
     }
 
@@ -215,53 +880,173 @@ impl Environment {
         self.ports.get(unit_name).copied()
     }
 
-    /// Short display name derived from the project directory.
+    /// Whether the unit's port was accepting connections at the last
+    /// probe; `None` if it has no port or hasn't been probed.
+    pub fn listening_for(&self, unit_name: &str) -> Option<bool> {
+        self.listening.get(unit_name).copied()
+    }
+
+    pub fn port_conflict_for(&self, unit_name: &str) -> Option<&PortConflict> {
+        self.port_conflicts.get(unit_name)
+    }
+
+    /// The latest health check result for a unit, if it has a check
+    /// that has run.
+    pub fn health_for(&self, unit_name: &str) -> Option<Health> {
+        self.health.get(unit_name).copied()
+    }
+
+    /// Links for a unit, default first. Empty if it has neither a port
+    /// nor a `*_URL` key.
+    pub fn links_for(&self, unit_name: &str) -> &[Link] {
+        self.links.get(unit_name).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Short display name: `NAME` if set, otherwise the project
+    /// directory's basename, lengthened by [`load_all`] when another
+    /// environment would show the same thing (`client-a/app`).
     pub fn display_name(&self) -> &str {
-        self.dir
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown")
+        &self.label
+    }
+
+    /// Re-check liveness now, e.g. right before signaling `pid`, since
+    /// `alive` is only as fresh as the last load.
+    pub fn check_alive(&self) -> bool {
+        let started = (self.started > 0).then_some(self.started);
+        liveness::check(self.pid, started) == Liveness::Alive
+    }
+
+    /// Seconds since started (0 if `STARTED` was missing or in the future).
+    pub fn elapsed_secs(&self) -> u64 {
+        now_secs().saturating_sub(self.started)
     }
 
     /// Elapsed time since started, as a human-readable string.
     pub fn elapsed_string(&self) -> String {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
-        let elapsed = now.saturating_sub(self.started);
-        if elapsed < 60 {
-            format!("{}s", elapsed)
-        } else if elapsed < 3600 {
-            format!("{}m", elapsed / 60)
-        } else if elapsed < 86400 {
-            format!("{}h {}m", elapsed / 3600, (elapsed % 3600) / 60)
-        } else {
-            format!("{}d", elapsed / 86400)
+        format_duration(self.elapsed_secs())
+    }
+}
+
+/// A directory entry's mtime in epoch seconds.
+fn modified_secs(entry: &fs::DirEntry) -> Option<u64> {
+    let modified = entry.metadata().ok()?.modified().ok()?;
+    modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+/// Remembers when each unit was first seen in its current state, so
+/// [`UnitStatus::state_since`] survives rewrites that only change the
+/// detail or progress (which bump the mtime), and is filled in even when
+/// the mtime is unavailable.
+#[derive(Debug, Default)]
+pub struct SinceTracker {
+    seen: HashMap<(String, String), (State, u64)>,
+}
+
+impl SinceTracker {
+    pub fn new() -> SinceTracker {
+        SinceTracker::default()
+    }
+
+    /// Settle `state_since` on every unit in `envs`. Call after each
+    /// [`load_all`].
+    ///
+    /// A writer's `SINCE` always wins. Otherwise a unit whose state hasn't
+    /// changed since the last call keeps its earlier time, and a new or
+    /// changed unit takes the status file's mtime, or now.
+    pub fn apply(&mut self, envs: &mut [Environment]) {
+        let now = now_secs();
+        let mut seen = HashMap::new();
+        for env in envs.iter_mut() {
+            for unit in &mut env.units {
+                let key = (env.id.clone(), unit.name.clone());
+                let since = match (unit.since, self.seen.get(&key)) {
+                    (Some(since), _) => since,
+                    (None, Some((state, since))) if *state == unit.state => *since,
+                    (None, _) => unit.state_since.unwrap_or(now),
+                };
+                unit.state_since = Some(since);
+                seen.insert(key, (unit.state.clone(), since));
+            }
         }
+        self.seen = seen;
+    }
+}
+
+/// Current Unix time in seconds.
+pub fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Compact human-readable duration: `42s`, `5m`, `2h 10m`, `3d`.
+pub fn format_duration(secs: u64) -> String {
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else if secs < 86400 {
+        format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
+    } else {
+        format!("{}d", secs / 86400)
     }
 }
 
-/// Returns the path to the dev-runner registry directory (~/.dev-runner/),
-/// or `None` if the home directory cannot be determined.
+/// Environment variables consulted, in order, when no explicit state
+/// directory is given. `SUTRA_STATE_DIR` wins over the tool-neutral
+/// `DEV_RUNNER_DIR` so a sutra-specific override can't be shadowed by a
+/// shared one.
+pub const STATE_DIR_ENV_VARS: [&str; 2] = ["SUTRA_STATE_DIR", "DEV_RUNNER_DIR"];
+
+/// Returns the path to the dev-runner registry directory, resolved from
+/// the environment (see [`resolve_state_dir`]), or `None` if no override
+/// is set and the home directory cannot be determined.
 pub fn state_dir() -> Option<PathBuf> {
+    resolve_state_dir(None)
+}
+
+/// Resolve the registry directory.
+///
+/// Resolution order:
+/// 1. `explicit` (e.g. the `--state-dir` flag)
+/// 2. `$SUTRA_STATE_DIR`
+/// 3. `$DEV_RUNNER_DIR`
+/// 4. `~/.dev-runner`
+///
+/// Empty values are skipped, so `SUTRA_STATE_DIR=` behaves like unset.
+pub fn resolve_state_dir(explicit: Option<&Path>) -> Option<PathBuf> {
+    if let Some(dir) = explicit.filter(|d| !d.as_os_str().is_empty()) {
+        return Some(dir.to_path_buf());
+    }
+    for var in STATE_DIR_ENV_VARS {
+        if let Some(value) = std::env::var_os(var).filter(|v| !v.is_empty()) {
+            return Some(PathBuf::from(value));
+        }
+    }
+    default_state_dir()
+}
+
+/// `~/.dev-runner`, the registry used when nothing overrides it.
+pub fn default_state_dir() -> Option<PathBuf> {
     dirs::home_dir().map(|h| h.join(".dev-runner"))
 }
 
 /// Returns true if a filename looks like a hex-only meta file (no dots, not hidden).
-fn is_meta_file(name: &str) -> bool {
+/// Also the validity check for environment IDs on the writer side.
+pub fn is_meta_file(name: &str) -> bool {
     !name.is_empty()
         && !name.starts_with('.')
         && !name.contains('.')
         && name.chars().all(|c| c.is_ascii_hexdigit())
 }
 
-/// Load all environment instances from the registry.
-pub fn load_all() -> Vec<Environment> {
-    let Some(dir) = state_dir() else {
-        return Vec::new();
-    };
-    let Ok(entries) = fs::read_dir(&dir) else {
+/// Load all environment instances from the registry directory `dir`.
+pub fn load_all(dir: &Path) -> Vec<Environment> {
+    let Ok(entries) = fs::read_dir(dir) else {
         return Vec::new();
     };
 
@@ -277,9 +1062,102 @@ pub fn load_all() -> Vec<Environment> {
     }
 
     envs.sort_by(|a, b| a.dir.cmp(&b.dir));
+    disambiguate_labels(&mut envs);
     envs
 }
 
+/// The last `n` components of `dir`, joined with `/`.
+fn path_suffix(dir: &Path, n: usize) -> String {
+    let parts: Vec<String> = dir
+        .components()
+        .filter_map(|c| match c {
+            std::path::Component::Normal(p) => Some(p.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+    if parts.is_empty() {
+        return dir.display().to_string();
+    }
+    parts[parts.len().saturating_sub(n)..].join("/")
+}
+
+/// Lengthen the path-derived labels of environments without a `NAME`
+/// until no two environments share a label, one parent directory at a
+/// time, so two checkouts called `app` become `client-a/app` and
+/// `client-b/app`. Environments with the same `DIR` stay identical.
+fn disambiguate_labels(envs: &mut [Environment]) {
+    let mut depth = vec![1usize; envs.len()];
+    loop {
+        let mut changed = false;
+        for i in 0..envs.len() {
+            if envs[i].name.is_some() {
+                continue;
+            }
+            let clash = envs.iter().enumerate().any(|(j, other)| {
+                j != i && other.label == envs[i].label && other.dir != envs[i].dir
+            });
+            let max_depth = envs[i].dir.components().count();
+            if clash && depth[i] < max_depth {
+                depth[i] += 1;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        for (env, depth) in envs.iter_mut().zip(&depth) {
+            if env.name.is_none() {
+                env.label = path_suffix(&env.dir, *depth);
+            }
+        }
+    }
+}
+
+/// Canonical environment ID for a project directory: the first 16 hex
+/// chars of the SHA-256 of the canonicalized path.
+///
+/// Canonicalizing first means `.`, `$PWD`, a trailing slash or a symlink
+/// to the project all produce the same ID. Fails if the path doesn't
+/// exist.
+pub fn env_id_for(path: &Path) -> io::Result<String> {
+    Ok(path_id(&fs::canonicalize(path)?))
+}
+
+/// The first 16 hex chars of the SHA-256 of `path`, taken as is.
+pub fn path_id(path: &Path) -> String {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(path.as_os_str().as_encoded_bytes());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Find the environment for the project containing `path`.
+///
+/// Tries the canonical ID ([`env_id_for`]) of `path` and each of its
+/// ancestors first; failing that, falls back to the environment whose
+/// `DIR` is `path` or its closest ancestor, which covers writers that
+/// derive their ID some other way. Both sides are canonicalized when
+/// possible, so symlinked and trailing-slash spellings match.
+pub fn find_for_dir<'a>(envs: &'a [Environment], path: &Path) -> Option<&'a Environment> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    for ancestor in path.ancestors() {
+        let Ok(id) = env_id_for(ancestor) else {
+            continue;
+        };
+        if let Some(env) = envs.iter().find(|e| e.id == id) {
+            return Some(env);
+        }
+    }
+    envs.iter()
+        .filter_map(|env| {
+            let dir = fs::canonicalize(&env.dir).unwrap_or_else(|_| env.dir.clone());
+            path.starts_with(&dir)
+                .then(|| ((dir.components().count(), env.alive), env))
+        })
+        // Deepest match wins; among equal dirs, prefer a live environment.
+        .max_by_key(|(rank, _)| *rank)
+        .map(|(_, env)| env)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,6 +1179,18 @@ mod tests {
         assert_eq!(State::parse("Ready"), State::Other("Ready".into()));
     }
 
+    #[test]
+    fn from_user_str_accepts_only_known_states() {
+        for s in USER_STATES {
+            assert_eq!(State::from_user_str(s).unwrap().to_string(), s);
+        }
+        assert!(State::from_user_str("dead").unwrap().is_dead());
+        let err = State::from_user_str("Ready").unwrap_err();
+        assert!(err.starts_with("unknown state \"Ready\""), "{err}");
+        assert!(err.ends_with("failed, stopped, dead"), "{err}");
+        assert!(State::from_user_str("").is_err());
+    }
+
     #[test]
     fn unit_parse_state_only() {
         let u = UnitStatus::parse("server", "ready");
@@ -351,6 +1241,325 @@ mod tests {
         assert_eq!(u.state, State::None);
     }
 
+    #[test]
+    fn state_serializes_as_keyword() {
+        let json = serde_json::to_string(&State::Building).unwrap();
+        assert_eq!(json, "\"building\"");
+        let back: State = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, State::Building);
+        let none: State = serde_json::from_str("\"\"").unwrap();
+        assert_eq!(none, State::None);
+        let other: State = serde_json::from_str("\"done\"").unwrap();
+        assert_eq!(other, State::Other("done".into()));
+    }
+
+    #[test]
+    fn unit_display_round_trips() {
+        let u = UnitStatus::new("server", State::Building, Some("cargo"));
+        assert_eq!(u.to_string(), "building: cargo");
+        let back = UnitStatus::parse("server", &u.to_string());
+        assert_eq!(back.state, State::Building);
+        assert_eq!(back.detail.as_deref(), Some("cargo"));
+
+        let u = UnitStatus::new("server", State::Ready, None);
+        assert_eq!(u.to_string(), "ready");
+    }
+
+    #[test]
+    fn unit_display_flattens_multiline_detail() {
+        let u = UnitStatus::new("server", State::Failed, Some("exit code 1\npanicked"));
+        assert_eq!(u.to_string(), "failed: exit code 1 panicked");
+    }
+
+    #[test]
+    fn unit_parse_v2_fields() {
+        let u = UnitStatus::parse(
+            "server",
+            "building: cargo\nPROGRESS=42/120\nSINCE=1700000000\nLOG=/tmp/server.log\nERRORS=3\n",
+        );
+        assert_eq!(u.state, State::Building);
+        assert_eq!(u.detail.as_deref(), Some("cargo"));
+        assert_eq!(
+            u.progress,
+            Some(Progress {
+                current: 42,
+                total: 120
+            })
+        );
+        assert_eq!(u.since, Some(1700000000));
+        assert_eq!(u.log, Some(PathBuf::from("/tmp/server.log")));
+        assert_eq!(u.errors, Some(3));
+
+        let back = UnitStatus::parse("server", &u.to_string());
+        assert_eq!(back.to_string(), u.to_string());
+        assert_eq!(back.progress, u.progress);
+    }
+
+    #[test]
+    fn unit_parse_v1_multiline_detail_unchanged() {
+        // Non-field lines stay part of the detail, as before v2.
+        let u = UnitStatus::parse("server", "failed: exit code 1\npanicked at main.rs\n");
+        assert_eq!(
+            u.detail.as_deref(),
+            Some("exit code 1\npanicked at main.rs")
+        );
+        assert_eq!(u.progress, None);
+        // A detail containing '=' on the first line is still a detail.
+        let u = UnitStatus::parse("server", "building:FOO=bar");
+        assert_eq!(u.detail.as_deref(), Some("FOO=bar"));
+    }
+
+    #[test]
+    fn unit_parse_v1_detail_with_key_value_lines_round_trips() {
+        let u = UnitStatus::parse("server", "failed: missing tool\nPATH=/usr/bin\n");
+        assert_eq!(u.detail.as_deref(), Some("missing tool\nPATH=/usr/bin"));
+        assert_eq!(u.to_string(), "failed: missing tool PATH=/usr/bin");
+        let back = UnitStatus::parse("server", &u.to_string());
+        assert_eq!(back.state, State::Failed);
+        assert_eq!(back.detail.as_deref(), Some("missing tool PATH=/usr/bin"));
+        assert_eq!(back.to_string(), u.to_string());
+    }
+
+    #[test]
+    fn display_progress_falls_back_to_detail() {
+        let u = UnitStatus::parse("wasm", "building: [12/120] Compiling serde");
+        assert_eq!(
+            u.display_progress(),
+            Some(Progress {
+                current: 12,
+                total: 120
+            })
+        );
+        let u = UnitStatus::parse("wasm", "building: 42%\nPROGRESS=50/100");
+        assert_eq!(u.display_progress().unwrap().current, 50);
+        // Only building/starting details are scanned.
+        assert_eq!(
+            UnitStatus::parse("wasm", "failed: 3/4 tests").display_progress(),
+            None
+        );
+        assert_eq!(
+            UnitStatus::parse("wasm", "building: src/main.rs").display_progress(),
+            None
+        );
+        // Only the first word counts, as STATE_SPEC.md documents.
+        assert_eq!(
+            UnitStatus::parse("wasm", "building: crate 3/4 of serde").display_progress(),
+            None
+        );
+    }
+
+    #[test]
+    fn progress_parses_fraction_and_percent() {
+        assert_eq!(Progress::parse("12/120").unwrap().fraction(), 0.1);
+        assert_eq!(Progress::parse("42%").unwrap().to_string(), "42%");
+        assert_eq!(Progress::parse(" 200/100 ").unwrap().fraction(), 1.0);
+        assert_eq!(Progress::parse("1/0"), None);
+        assert_eq!(Progress::parse("lots"), None);
+    }
+
+    #[test]
+    fn meta_parse_and_render_round_trip() {
+        let content = "DIR=/code/app\nPID=42\nSTARTED=1700000000\nSERVER_PORT=3000\nVITE_PORT=5173\nVITE_PID=43\nJUNK\nOTHER=1\n";
+        let meta = Meta::parse(content).unwrap();
+        assert_eq!(meta.dir, PathBuf::from("/code/app"));
+        assert_eq!(meta.pid, 42);
+        assert_eq!(meta.started, Some(1700000000));
+        assert_eq!(meta.ports.get("server"), Some(&3000));
+        assert_eq!(meta.ports.get("vite"), Some(&5173));
+        assert_eq!(meta.pids.get("vite"), Some(&43));
+        assert_eq!(Meta::parse(&meta.to_string()), Some(meta));
+    }
+
+    #[test]
+    fn meta_parses_health_checks() {
+        let meta = Meta::parse(
+            "DIR=/code/app\nPID=42\nSERVER_HEALTH=http://127.0.0.1:3000/health\n\
+             SERVER_HEALTH_INTERVAL=10s\nSERVER_HEALTH_TIMEOUT=500ms\n\
+             VITE_HEALTH_INTERVAL=1s\nAPI_HEALTH=https://api.localhost/\n",
+        )
+        .unwrap();
+        let server = &meta.health_checks["server"];
+        assert_eq!(server.url, "http://127.0.0.1:3000/health");
+        assert_eq!(server.interval(), Duration::from_secs(10));
+        assert_eq!(server.timeout(), Duration::from_millis(500));
+        // No URL, or one the prober can't fetch.
+        assert_eq!(meta.health_checks.len(), 1);
+        assert_eq!(Meta::parse(&meta.to_string()), Some(meta));
+    }
+
+    #[test]
+    fn meta_parses_display_keys() {
+        let meta =
+            Meta::parse("DIR=/code/app\nPID=42\nNAME=Client A\nDESCRIPTION=  \nICON=🚀\n").unwrap();
+        assert_eq!(meta.name.as_deref(), Some("Client A"));
+        assert_eq!(meta.description, None);
+        assert_eq!(meta.icon.as_deref(), Some("🚀"));
+        assert_eq!(Meta::parse(&meta.to_string()), Some(meta));
+    }
+
+    #[test]
+    fn meta_resolves_unit_links() {
+        let meta = Meta::parse(
+            "DIR=/code/app\nPID=42\nSERVER_PORT=3000\nSERVER_URL_DOCS=/docs\n\
+             SERVER_URL_ADMIN=https://admin.localhost/\nVITE_URL=https://app.localhost:5173/\n\
+             API_URL_DOCS=/no-port\nBAD_URL=not a url\n",
+        )
+        .unwrap();
+        assert_eq!(Meta::parse(&meta.to_string()), Some(meta.clone()));
+
+        let links = meta.links();
+        let server: Vec<(Option<&str>, &str)> = links["server"]
+            .iter()
+            .map(|l| (l.label.as_deref(), l.url.as_str()))
+            .collect();
+        assert_eq!(
+            server,
+            [
+                (None, "http://localhost:3000"),
+                (Some("admin"), "https://admin.localhost/"),
+                (Some("docs"), "http://localhost:3000/docs"),
+            ]
+        );
+        assert_eq!(links["vite"][0].url, "https://app.localhost:5173/");
+        assert!(!links.contains_key("api"));
+        assert!(!links.contains_key("bad"));
+    }
+
+    #[test]
+    fn labels_disambiguate_same_basename() {
+        let tmp = tempfile::tempdir().unwrap();
+        let write = |id: &str, content: &str| fs::write(tmp.path().join(id), content).unwrap();
+        write("a1", "DIR=/code/client-a/app\nPID=1\n");
+        write("b2", "DIR=/code/client-b/app\nPID=1\n");
+        write("c3", "DIR=/code/web\nPID=1\n");
+        write("d4", "DIR=/code/client-c/app\nPID=1\nNAME=Client C\n");
+
+        let labels: Vec<String> = load_all(tmp.path())
+            .into_iter()
+            .map(|e| e.display_name().to_string())
+            .collect();
+        assert_eq!(labels, ["client-a/app", "client-b/app", "Client C", "web"]);
+    }
+
+    #[test]
+    fn meta_parse_requires_dir_and_pid() {
+        assert!(Meta::parse("DIR=/code/app\n").is_none());
+        assert!(Meta::parse("PID=42\n").is_none());
+        assert!(Meta::parse("DIR=/code/app\nPID=notanumber\n").is_none());
+    }
+
+    #[test]
+    fn since_tracker_holds_time_until_state_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let meta = Meta::new(tmp.path().to_path_buf(), std::process::id());
+        fs::write(tmp.path().join("ab12"), meta.to_string()).unwrap();
+        let status = tmp.path().join("ab12.server.status");
+        let mut tracker = SinceTracker::new();
+        let load = |tracker: &mut SinceTracker, content: &str| {
+            fs::write(&status, content).unwrap();
+            let mut envs = load_all(tmp.path());
+            tracker.apply(&mut envs);
+            envs[0].units[0].state_since.unwrap()
+        };
+
+        let first = load(&mut tracker, "building: 1/10");
+        assert!(first.abs_diff(now_secs()) < 5);
+        // Pretend the first observation was long ago: a progress-only
+        // rewrite keeps it, a state change takes the new mtime.
+        tracker
+            .seen
+            .values_mut()
+            .for_each(|(_, since)| *since = 100);
+        assert_eq!(load(&mut tracker, "building: 2/10"), 100);
+        assert_ne!(load(&mut tracker, "ready"), 100);
+        assert_eq!(load(&mut tracker, "failed\nSINCE=42"), 42);
+    }
+
+    #[test]
+    fn active_unit_with_gone_pid_is_dead() {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let gone = child.id();
+        child.wait().unwrap();
+
+        let tmp = tempfile::tempdir().unwrap();
+        let mut meta = Meta::new(tmp.path().to_path_buf(), std::process::id());
+        meta.pids.insert("vite".into(), gone);
+        fs::write(tmp.path().join("ab12"), meta.to_string()).unwrap();
+        let write = |unit: &str, content: String| {
+            fs::write(tmp.path().join(format!("ab12.{unit}.status")), content).unwrap()
+        };
+        write("server", format!("ready\nPID={}", std::process::id()));
+        write("vite", "ready".into());
+        write("worker", format!("failed\nPID={gone}"));
+
+        let env = &load_all(tmp.path())[0];
+        let dead: Vec<(&str, bool)> = env
+            .units
+            .iter()
+            .map(|u| (u.name.as_str(), u.dead))
+            .collect();
+        assert_eq!(dead, [("server", false), ("vite", true), ("worker", false)]);
+        let vite = &env.units[1];
+        assert_eq!(vite.pid, Some(gone));
+        assert_eq!(vite.observed_state().to_string(), "dead");
+        assert_eq!(vite.style_state(), &State::Failed);
+    }
+
+    #[test]
+    fn find_for_dir_prefers_deepest_ancestor() {
+        let tmp = tempfile::tempdir().unwrap();
+        let outer = tmp.path().join("outer");
+        let inner = outer.join("inner");
+        fs::create_dir_all(inner.join("src")).unwrap();
+        let env = |id: &str, dir: &Path| Environment {
+            id: id.into(),
+            dir: dir.to_path_buf(),
+            pid: 1,
+            ports: HashMap::new(),
+            links: HashMap::new(),
+            started: 0,
+            alive: true,
+            stale_pid: false,
+            units: Vec::new(),
+            listening: HashMap::new(),
+            port_conflicts: HashMap::new(),
+            health_checks: HashMap::new(),
+            health: HashMap::new(),
+            name: None,
+            description: None,
+            icon: None,
+            label: path_suffix(dir, 1),
+        };
+        let envs = vec![env("a1", &outer), env("b2", &inner)];
+
+        assert_eq!(find_for_dir(&envs, &inner.join("src")).unwrap().id, "b2");
+        assert_eq!(find_for_dir(&envs, &outer).unwrap().id, "a1");
+        assert!(find_for_dir(&envs, tmp.path()).is_none());
+    }
+
+    #[test]
+    fn env_id_is_stable_across_path_spellings() {
+        let tmp = tempfile::tempdir().unwrap();
+        let project = tmp.path().join("project");
+        fs::create_dir(&project).unwrap();
+        let link = tmp.path().join("link");
+        std::os::unix::fs::symlink(&project, &link).unwrap();
+
+        let id = env_id_for(&project).unwrap();
+        assert_eq!(id.len(), 16);
+        assert!(is_meta_file(&id));
+        assert_eq!(env_id_for(&link).unwrap(), id);
+        assert_eq!(env_id_for(&project.join("")).unwrap(), id);
+        assert_eq!(env_id_for(&project.join("..").join("project")).unwrap(), id);
+        assert!(env_id_for(&tmp.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn explicit_state_dir_wins() {
+        let dir = Path::new("/tmp/sutra-explicit");
+        assert_eq!(resolve_state_dir(Some(dir)), Some(dir.to_path_buf()));
+    }
+
     #[test]
     fn meta_filename_validation() {
         assert!(is_meta_file("a"));
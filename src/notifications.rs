@@ -3,9 +3,13 @@ use std::mem;
 use std::sync::mpsc;
 use std::thread::{self, JoinHandle};
 
-use crate::model::{Environment, State};
+use crate::journal::{self, Journal};
+use crate::model::{self, Environment, State};
+use crate::stats::Stats;
 
 /// Action sent to the background audio/speech thread.
+// The payload is only consumed by the macOS audio/speech backends.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
 enum Action {
     SoundAndSpeak { sound: &'static str, text: String },
     Shutdown,
@@ -24,7 +28,18 @@ pub struct Notifier {
     pub muted_units: HashSet<String>,
     pub global_notifications_off: bool,
     pub notifications_off_units: HashSet<String>,
+    /// Where observed transitions are recorded; `None` disables it.
+    pub journal: Option<Journal>,
+    /// Notify when a build or startup is much slower than its median.
+    /// Still subject to the notification toggles.
+    pub regression_alerts: bool,
+    /// Notify when a `ready` unit's port stops accepting connections
+    /// (see [`PortProber`](crate::probe::PortProber)). Still subject to
+    /// the notification toggles.
+    pub port_alerts: bool,
+    stats: Stats,
     prev_states: HashMap<(String, String), State>,
+    prev_listening: HashMap<(String, String), bool>,
     first_load: bool,
 }
 
@@ -52,11 +67,27 @@ impl Notifier {
             muted_units: HashSet::new(),
             global_notifications_off: false,
             notifications_off_units: HashSet::new(),
+            journal: None,
+            regression_alerts: true,
+            port_alerts: true,
+            stats: Stats::default(),
             prev_states: HashMap::new(),
+            prev_listening: HashMap::new(),
             first_load: true,
         }
     }
 
+    /// Record transitions to `journal`, and seed the duration stats from
+    /// what it already holds.
+    pub fn with_journal(mut self, journal: Option<Journal>) -> Self {
+        self.stats = journal
+            .as_ref()
+            .map(|j| Stats::from_entries(&j.read()))
+            .unwrap_or_default();
+        self.journal = journal;
+        self
+    }
+
     fn audio_thread(rx: mpsc::Receiver<Action>) {
         // Init rodio (optional — skip if unavailable)
         #[cfg(target_os = "macos")]
@@ -110,17 +141,29 @@ impl Notifier {
     /// Detect transitions and fire sounds/notifications.
     /// Call this after each load_all().
     pub fn process(&mut self, envs: &[Environment]) {
-        // Build current state map
+        // Build current state map. Only the state is tracked, so detail,
+        // progress and other v2 fields never cause a transition on their own.
+        // A dead unit counts as a transition to `dead`.
         let mut current: HashMap<(String, String), State> = HashMap::new();
         for env in envs {
             for unit in &env.units {
-                current.insert((env.id.clone(), unit.name.clone()), unit.state.clone());
+                current.insert((env.id.clone(), unit.name.clone()), unit.observed_state());
             }
         }
 
+        let listening: HashMap<(String, String), bool> = envs
+            .iter()
+            .flat_map(|env| {
+                env.listening
+                    .iter()
+                    .map(|(unit, up)| ((env.id.clone(), unit.clone()), *up))
+            })
+            .collect();
+
         if self.first_load {
             // First load: snapshot states, no sounds
             self.prev_states = current;
+            self.prev_listening = listening;
             self.first_load = false;
             return;
         }
@@ -128,9 +171,12 @@ impl Notifier {
         // Diff against prev_states — collect all transitions, then batch audio.
         let mut batched_speeches: Vec<String> = Vec::new();
         let mut best_sound: Option<&'static str> = None;
+        let mut entries: Vec<journal::Entry> = Vec::new();
+        let now = model::now_secs();
 
         for (key, new_state) in &current {
-            let changed = match self.prev_states.get(key) {
+            let old_state = self.prev_states.get(key);
+            let changed = match old_state {
                 Some(old_state) => !state_variant_eq(old_state, new_state),
                 None => true, // new unit appeared
             };
@@ -139,8 +185,24 @@ impl Notifier {
                 continue;
             }
 
+            if let Some((env, unit)) = find_unit(envs, key) {
+                entries.push(journal::Entry {
+                    ts: now,
+                    env: env.id.clone(),
+                    dir: env.dir.clone(),
+                    unit: unit.name.clone(),
+                    old: old_state.cloned(),
+                    new: new_state.clone(),
+                    detail: unit.detail.clone(),
+                    since: unit.state_since,
+                });
+            }
+
             // Determine sound/speech for this transition
             let (sound, speech) = match new_state {
+                State::Other(_) if new_state.is_dead() => {
+                    (Some("Basso"), Some(format!("{} {}", key.1, new_state)))
+                }
                 State::None | State::Other(_) | State::Stopped => (None, None),
                 State::Building | State::Starting => {
                     (Some("Submarine"), Some(format!("{} {}", key.1, new_state)))
@@ -187,6 +249,72 @@ impl Notifier {
             }
         }
 
+        entries.sort_by(|a, b| (&a.env, &a.unit).cmp(&(&b.env, &b.unit)));
+        if let Some(journal) = &self.journal {
+            let _ = journal.append(&entries);
+        }
+        for entry in &entries {
+            for phase in self.stats.record(entry) {
+                let Some(summary) = self.stats.summary(&entry.dir, &entry.unit, phase) else {
+                    continue;
+                };
+                let Some(ratio) = summary.regression() else {
+                    continue;
+                };
+                let uk = unit_key(&entry.env, &entry.unit);
+                if !self.regression_alerts
+                    || self.global_notifications_off
+                    || self.notifications_off_units.contains(&uk)
+                {
+                    continue;
+                }
+                #[cfg(target_os = "macos")]
+                {
+                    let _ = mac_notification_sys::send_notification(
+                        &format!("sutra — {}", entry.unit),
+                        None,
+                        &format!(
+                            "{phase} {ratio:.1}× slower than median ({} vs {})",
+                            model::format_duration(summary.last),
+                            model::format_duration(summary.median)
+                        ),
+                        None,
+                    );
+                }
+                #[cfg(not(target_os = "macos"))]
+                let _ = ratio;
+            }
+        }
+
+        for (key, up) in &listening {
+            if *up || self.prev_listening.get(key) != Some(&true) {
+                continue;
+            }
+            let Some((env, unit)) = find_unit(envs, key) else {
+                continue;
+            };
+            let uk = unit_key(&key.0, &key.1);
+            if unit.observed_state() != State::Ready
+                || !self.port_alerts
+                || self.global_notifications_off
+                || self.notifications_off_units.contains(&uk)
+            {
+                continue;
+            }
+            #[cfg(target_os = "macos")]
+            if let Some(port) = env.port_for(&unit.name) {
+                let _ = mac_notification_sys::send_notification(
+                    &format!("sutra — {}", unit.name),
+                    None,
+                    &format!("ready, but nothing is listening on :{port}"),
+                    None,
+                );
+            }
+            #[cfg(not(target_os = "macos"))]
+            let _ = env;
+        }
+        self.prev_listening = listening;
+
         // Send one batched action: single sound + combined speech utterance
         if let Some(sound) = best_sound {
             if !batched_speeches.is_empty() {
@@ -198,6 +326,12 @@ impl Notifier {
         self.prev_states = current;
     }
 
+    /// Build and startup durations seen so far, including earlier
+    /// sessions' from the journal.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
     pub fn toggle_global_mute(&mut self) {
         self.global_mute = !self.global_mute;
     }
@@ -239,6 +373,16 @@ impl Drop for Notifier {
     }
 }
 
+/// Look up the environment and unit behind a `(env_id, unit_name)` key.
+fn find_unit<'a>(
+    envs: &'a [Environment],
+    key: &(String, String),
+) -> Option<(&'a Environment, &'a model::UnitStatus)> {
+    let env = envs.iter().find(|e| e.id == key.0)?;
+    let unit = env.units.iter().find(|u| u.name == key.1)?;
+    Some((env, unit))
+}
+
 /// Compare two State values by variant, also comparing the inner string for Other.
 fn state_variant_eq(a: &State, b: &State) -> bool {
     match (a, b) {
@@ -0,0 +1,134 @@
+use std::fmt;
+
+use regex::Regex;
+
+use crate::model::State;
+
+/// Longest detail a matched line is allowed to put in a status file.
+const MAX_DETAIL_CHARS: usize = 200;
+
+/// Maps output lines matching `regex` to `state`.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub state: State,
+    pub regex: Regex,
+}
+
+impl Rule {
+    /// `state` is validated with [`State::from_user_str`].
+    pub fn new(state: &str, pattern: &str) -> Result<Rule, String> {
+        let state = State::from_user_str(state)?;
+        let regex = Regex::new(pattern).map_err(|e| format!("invalid pattern {pattern:?}: {e}"))?;
+        Ok(Rule { state, regex })
+    }
+
+    /// Parse `STATE=REGEX`, e.g. `ready=Local:.*http://`.
+    pub fn parse(spec: &str) -> Result<Rule, String> {
+        let (state, pattern) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("expected STATE=REGEX, got {spec:?}"))?;
+        Rule::new(state.trim(), pattern)
+    }
+}
+
+impl PartialEq for Rule {
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state && self.regex.as_str() == other.regex.as_str()
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.state, self.regex.as_str())
+    }
+}
+
+/// Match one line of output against `rules`, first rule wins. Returns the
+/// new state and the line cleaned up for use as the status detail.
+///
+/// Color codes are stripped before matching, so patterns can be written
+/// against what the user sees in the terminal.
+pub fn match_line(rules: &[Rule], line: &str) -> Option<(State, String)> {
+    let plain = strip_ansi(line);
+    let rule = rules.iter().find(|r| r.regex.is_match(&plain))?;
+    let detail: String = plain.trim().chars().take(MAX_DETAIL_CHARS).collect();
+    Some((rule.state.clone(), detail))
+}
+
+/// Remove ANSI CSI (`ESC [ … final`) and OSC (`ESC ] … BEL|ST`) sequences.
+pub fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vite_rules() -> Vec<Rule> {
+        [
+            "ready=Local:.*http://",
+            "building=Compiling",
+            r"failed=error\[",
+        ]
+        .iter()
+        .map(|s| Rule::parse(s).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn first_matching_rule_wins_with_line_as_detail() {
+        let rules = vite_rules();
+        let (state, detail) = match_line(&rules, "  Compiling sutra v0.1.3").unwrap();
+        assert_eq!(state, State::Building);
+        assert_eq!(detail, "Compiling sutra v0.1.3");
+        assert_eq!(
+            match_line(&rules, "error[E0308]: mismatched types").map(|m| m.0),
+            Some(State::Failed)
+        );
+        assert!(match_line(&rules, "hmr update /src/App.tsx").is_none());
+    }
+
+    #[test]
+    fn matches_through_color_codes() {
+        let line = "  \x1b[32m➜\x1b[39m  \x1b[1mLocal\x1b[22m:   \x1b[36mhttp://localhost:\x1b[1m5173\x1b[22m/\x1b[39m";
+        let (state, detail) = match_line(&vite_rules(), line).unwrap();
+        assert_eq!(state, State::Ready);
+        assert_eq!(detail, "➜  Local:   http://localhost:5173/");
+    }
+
+    #[test]
+    fn rejects_unknown_states_and_bad_patterns() {
+        assert!(Rule::parse("Ready=x").is_err());
+        assert!(Rule::parse("ready=(").is_err());
+        assert!(Rule::parse("ready").is_err());
+    }
+}
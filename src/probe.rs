@@ -0,0 +1,761 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::model::{Environment, Health, HealthCheck, PortConflict, PortOwner, State, UnitStatus};
+use crate::sockets::{ListenerCache, SocketOwner};
+
+/// How often [`PortProber`] re-checks every port.
+pub const PORT_PROBE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Connect timeout per address. Loopback connects to a closed port are
+/// refused immediately, so this only bounds firewalled ones.
+pub const PORT_PROBE_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Loopback addresses a local dev server might be bound to. Servers
+/// differ on whether `localhost` means v4 or v6, so both are tried.
+pub const LOOPBACK: [IpAddr; 2] = [
+    IpAddr::V4(Ipv4Addr::LOCALHOST),
+    IpAddr::V6(Ipv6Addr::LOCALHOST),
+];
+
+/// A readiness check for a unit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Probe {
+    /// Something accepts TCP connections on this loopback port.
+    Tcp(u16),
+    /// An `http://` URL answers with a 2xx or 3xx status.
+    Http(String),
+}
+
+impl Probe {
+    /// Parse a probe spec: `tcp` (uses `port`), `tcp:<port>`, or an
+    /// `http://` URL.
+    pub fn parse(spec: &str, port: Option<u16>) -> Result<Probe, String> {
+        let spec = spec.trim();
+        if spec == "tcp" {
+            return port
+                .map(Probe::Tcp)
+                .ok_or_else(|| "`tcp` probe needs a port".to_string());
+        }
+        if let Some(p) = spec.strip_prefix("tcp:") {
+            return p
+                .parse()
+                .map(Probe::Tcp)
+                .map_err(|_| format!("invalid port in probe {spec:?}"));
+        }
+        if spec.starts_with("http://") {
+            HttpTarget::parse(spec)?;
+            return Ok(Probe::Http(spec.to_string()));
+        }
+        Err(format!(
+            "unknown probe {spec:?}; expected tcp, tcp:<port> or an http:// URL"
+        ))
+    }
+
+    /// Run the check once.
+    pub fn check(&self, timeout: Duration) -> bool {
+        match self {
+            Probe::Tcp(port) => tcp_listening(*port, timeout),
+            Probe::Http(url) => http_get(url, timeout).is_ok_and(|r| r.ok()),
+        }
+    }
+}
+
+impl fmt::Display for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Probe::Tcp(port) => write!(f, "tcp:{port}"),
+            Probe::Http(url) => f.write_str(url),
+        }
+    }
+}
+
+/// True if a TCP connect to `port` succeeds on any loopback address.
+pub fn tcp_listening(port: u16, timeout: Duration) -> bool {
+    LOOPBACK
+        .iter()
+        .any(|ip| TcpStream::connect_timeout(&SocketAddr::new(*ip, port), timeout).is_ok())
+}
+
+/// Outcome of a successful HTTP exchange.
+#[derive(Debug, Clone, Copy)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub latency: Duration,
+}
+
+impl HttpResponse {
+    /// See [`status_ok`].
+    pub fn ok(&self) -> bool {
+        status_ok(self.status)
+    }
+}
+
+/// Whether an HTTP status counts as up, for readiness probes and health
+/// checks alike: 2xx or 3xx. A 401 or 404 means the server answers but
+/// the endpoint isn't the one expected.
+pub fn status_ok(status: u16) -> bool {
+    (200..400).contains(&status)
+}
+
+/// Minimal `GET` over HTTP/1.0 — enough to probe a local dev server
+/// without pulling in an HTTP client. Only the status line is read.
+pub fn http_get(url: &str, timeout: Duration) -> io::Result<HttpResponse> {
+    let target =
+        HttpTarget::parse(url).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let started = Instant::now();
+    let addrs = (target.host.as_str(), target.port).to_socket_addrs()?;
+    let mut last_err = io::Error::new(io::ErrorKind::NotFound, "no address");
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(mut stream) => {
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                // One write, so a server that answers after the first
+                // read doesn't reset the connection mid-request.
+                let request = format!(
+                    "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+                    target.path, target.host_header
+                );
+                stream.write_all(request.as_bytes())?;
+                let mut buf = [0u8; 64];
+                let mut len = 0;
+                while len < buf.len() {
+                    let n = stream.read(&mut buf[len..])?;
+                    if n == 0 {
+                        break;
+                    }
+                    len += n;
+                    if buf[..len].contains(&b'\n') {
+                        break;
+                    }
+                }
+                let status = parse_status_line(&buf[..len]).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "malformed HTTP status line")
+                })?;
+                return Ok(HttpResponse {
+                    status,
+                    latency: started.elapsed(),
+                });
+            }
+            Err(e) => last_err = e,
+        }
+    }
+    Err(last_err)
+}
+
+fn parse_status_line(buf: &[u8]) -> Option<u16> {
+    let line = std::str::from_utf8(buf).ok()?.lines().next()?;
+    let mut parts = line.split_whitespace();
+    parts.next().filter(|v| v.starts_with("HTTP/"))?;
+    parts.next()?.parse().ok()
+}
+
+struct HttpTarget {
+    host: String,
+    host_header: String,
+    port: u16,
+    path: String,
+}
+
+impl HttpTarget {
+    fn parse(url: &str) -> Result<HttpTarget, String> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| format!("only http:// URLs are supported, got {url:?}"))?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("invalid port in {url:?}"))?,
+            ),
+            _ => (authority, 80),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(format!("missing host in {url:?}"));
+        }
+        Ok(HttpTarget {
+            host: host.to_string(),
+            host_header: authority.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+/// What the last probe found on one port.
+#[derive(Debug, Default)]
+struct PortState {
+    listening: bool,
+    owners: Vec<SocketOwner>,
+}
+
+/// Checks in the background whether anything accepts connections on the
+/// declared ports of live environments, so a unit that claims `ready`
+/// with nothing behind its port stands out, and who owns each port, so
+/// two environments fighting over one can be told apart.
+///
+/// Call [`PortProber::apply`] after each load, like
+/// [`SinceTracker::apply`](crate::model::SinceTracker::apply). Dropping
+/// the prober stops its thread.
+pub struct PortProber {
+    ports_tx: mpsc::Sender<BTreeSet<u16>>,
+    ports: BTreeSet<u16>,
+    results: Arc<Mutex<HashMap<u16, PortState>>>,
+}
+
+impl PortProber {
+    pub fn spawn() -> PortProber {
+        let (ports_tx, ports_rx) = mpsc::channel();
+        let results = Arc::new(Mutex::new(HashMap::new()));
+        let shared = results.clone();
+        thread::spawn(move || probe_loop(ports_rx, shared));
+        PortProber {
+            ports_tx,
+            ports: BTreeSet::new(),
+            results,
+        }
+    }
+
+    /// Probe the ports of the alive environments in `envs` from now on,
+    /// and fill in each one's [`Environment::listening`] and
+    /// [`Environment::port_conflicts`] from the latest results. Ports not
+    /// probed yet are left out of `listening`.
+    pub fn apply(&mut self, envs: &mut [Environment]) {
+        let ports: BTreeSet<u16> = envs
+            .iter()
+            .filter(|env| env.alive)
+            .flat_map(|env| env.ports.values().copied())
+            .collect();
+        if ports != self.ports {
+            let _ = self.ports_tx.send(ports.clone());
+            self.ports = ports;
+        }
+        let results = self.results.lock().unwrap();
+        for env in envs.iter_mut().filter(|env| env.alive) {
+            env.listening = env
+                .ports
+                .iter()
+                .filter_map(|(unit, port)| Some((unit.clone(), results.get(port)?.listening)))
+                .collect();
+        }
+        let owners: HashMap<u16, Vec<SocketOwner>> = results
+            .iter()
+            .map(|(port, state)| (*port, state.owners.clone()))
+            .collect();
+        find_conflicts(envs, &owners);
+    }
+}
+
+/// Probe every port each [`PORT_PROBE_INTERVAL`], and straight away when
+/// the set changes. Exits once the [`PortProber`] is dropped.
+fn probe_loop(
+    ports_rx: mpsc::Receiver<BTreeSet<u16>>,
+    results: Arc<Mutex<HashMap<u16, PortState>>>,
+) {
+    let mut ports = BTreeSet::new();
+    let mut listeners = ListenerCache::default();
+    loop {
+        match ports_rx.recv_timeout(PORT_PROBE_INTERVAL) {
+            Ok(new) => ports = new,
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        }
+        let mut owners = listeners.listeners(&ports);
+        let checked: HashMap<u16, PortState> = ports
+            .iter()
+            .map(|&port| {
+                let state = PortState {
+                    listening: tcp_listening(port, PORT_PROBE_TIMEOUT),
+                    owners: owners.remove(&port).unwrap_or_default(),
+                };
+                (port, state)
+            })
+            .collect();
+        *results.lock().unwrap() = checked;
+    }
+}
+
+/// Set [`Environment::port_conflicts`] on every alive environment: ports
+/// another alive environment also declares, and ports bound by a process
+/// that isn't this environment's.
+///
+/// A socket belongs to the environment whose `PID` or unit `PID` is the
+/// nearest in the owning process's lineage.
+fn find_conflicts(envs: &mut [Environment], owners: &HashMap<u16, Vec<SocketOwner>>) {
+    let roots: Vec<(usize, Vec<u32>)> = envs
+        .iter()
+        .enumerate()
+        .filter(|(_, env)| env.alive)
+        .map(|(i, env)| {
+            let pids = std::iter::once(env.pid)
+                .chain(env.units.iter().filter_map(|u| u.pid))
+                .collect();
+            (i, pids)
+        })
+        .collect();
+    let owning_env = |owner: &SocketOwner| {
+        owner.lineage.iter().find_map(|pid| {
+            roots
+                .iter()
+                .find(|(_, pids)| pids.contains(pid))
+                .map(|(i, _)| *i)
+        })
+    };
+
+    let mut found: Vec<HashMap<String, PortConflict>> = vec![HashMap::new(); envs.len()];
+    for (i, _) in &roots {
+        let env = &envs[*i];
+        for (unit, &port) in &env.ports {
+            let declared_by: Vec<String> = roots
+                .iter()
+                .filter(|(j, _)| j != i && envs[*j].ports.values().any(|&p| p == port))
+                .map(|(j, _)| envs[*j].label.clone())
+                .collect();
+            let owner = owners
+                .get(&port)
+                .into_iter()
+                .flatten()
+                .find_map(|owner| match owning_env(owner) {
+                    Some(j) if j == *i => None,
+                    Some(j) => Some(PortOwner::Environment {
+                        label: envs[j].label.clone(),
+                        pid: owner.pid,
+                    }),
+                    None => Some(PortOwner::Process {
+                        pid: owner.pid,
+                        name: owner.name.clone(),
+                    }),
+                });
+            if !declared_by.is_empty() || owner.is_some() {
+                found[*i].insert(
+                    unit.clone(),
+                    PortConflict {
+                        port,
+                        declared_by,
+                        owner,
+                    },
+                );
+            }
+        }
+    }
+    for (env, conflicts) in envs.iter_mut().zip(found) {
+        env.port_conflicts = conflicts;
+    }
+}
+
+/// (environment ID, unit name)
+type UnitKey = (String, String);
+
+/// Runs the `<UNIT>_HEALTH` checks of live environments in the
+/// background, each at its own interval.
+///
+/// Call [`HealthProber::apply`] after each load, before
+/// [`SinceTracker::apply`](crate::model::SinceTracker::apply) so
+/// synthetic units get a "since" too. Dropping the prober stops its
+/// thread.
+pub struct HealthProber {
+    checks_tx: mpsc::Sender<HashMap<UnitKey, HealthCheck>>,
+    checks: HashMap<UnitKey, HealthCheck>,
+    results: Arc<Mutex<HashMap<UnitKey, Health>>>,
+}
+
+impl HealthProber {
+    pub fn spawn() -> HealthProber {
+        let (checks_tx, checks_rx) = mpsc::channel();
+        let results = Arc::new(Mutex::new(HashMap::new()));
+        let shared = results.clone();
+        thread::spawn(move || health_loop(checks_rx, shared));
+        HealthProber {
+            checks_tx,
+            checks: HashMap::new(),
+            results,
+        }
+    }
+
+    /// Run the health checks of the alive environments in `envs` from now
+    /// on, and fill in each one's [`Environment::health`] from the latest
+    /// results.
+    ///
+    /// A checked unit whose writer publishes no state (no status file, or
+    /// an empty one) gets its [`Health::synthetic_state`].
+    pub fn apply(&mut self, envs: &mut [Environment]) {
+        let checks: HashMap<UnitKey, HealthCheck> = envs
+            .iter()
+            .filter(|env| env.alive)
+            .flat_map(|env| {
+                env.health_checks
+                    .iter()
+                    .map(|(unit, check)| ((env.id.clone(), unit.clone()), check.clone()))
+            })
+            .collect();
+        if checks != self.checks {
+            let _ = self.checks_tx.send(checks.clone());
+            self.checks = checks;
+        }
+        let results = self.results.lock().unwrap();
+        for env in envs.iter_mut().filter(|env| env.alive) {
+            env.health = env
+                .health_checks
+                .keys()
+                .filter_map(|unit| {
+                    let health = results.get(&(env.id.clone(), unit.clone()))?;
+                    Some((unit.clone(), *health))
+                })
+                .collect();
+            let mut added = false;
+            for (name, health) in &env.health {
+                let state = health.synthetic_state();
+                let detail = match state {
+                    State::Ready => None,
+                    _ => Some(format!("health check: {}", health.label())),
+                };
+                match env.units.iter_mut().find(|u| &u.name == name) {
+                    Some(unit) if unit.state == State::None => {
+                        unit.state = state;
+                        unit.detail = detail;
+                    }
+                    Some(_) => {}
+                    None => {
+                        env.units
+                            .push(UnitStatus::new(name, state, detail.as_deref()));
+                        added = true;
+                    }
+                }
+            }
+            if added {
+                env.units.sort_by(|a, b| a.name.cmp(&b.name));
+            }
+        }
+    }
+}
+
+/// What [`health_loop`] reacts to.
+enum HealthEvent {
+    /// The checks to run from now on, from [`HealthProber::apply`].
+    Checks(HashMap<UnitKey, HealthCheck>),
+    /// A check finished with this HTTP status (`None` if it got no
+    /// response) after this many milliseconds.
+    Checked(UnitKey, HealthCheck, Option<u16>, u64),
+    /// The [`HealthProber`] was dropped.
+    Stop,
+}
+
+/// Run each check when it's due, and all new or changed ones straight
+/// away. Every check runs on its own thread, so an unreachable URL that
+/// takes its whole timeout doesn't hold up the others, and results are
+/// recorded as they arrive. Exits once the [`HealthProber`] is dropped.
+fn health_loop(
+    checks_rx: mpsc::Receiver<HashMap<UnitKey, HealthCheck>>,
+    results: Arc<Mutex<HashMap<UnitKey, Health>>>,
+) {
+    let (tx, rx) = mpsc::channel();
+    {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for checks in checks_rx {
+                if tx.send(HealthEvent::Checks(checks)).is_err() {
+                    return;
+                }
+            }
+            let _ = tx.send(HealthEvent::Stop);
+        });
+    }
+
+    // When each check is next due; `None` while it's running.
+    let mut checks: HashMap<UnitKey, (HealthCheck, Option<Instant>)> = HashMap::new();
+    loop {
+        let next_due = checks.values().filter_map(|(_, due)| *due).min();
+        let received = match next_due {
+            Some(due) => rx.recv_timeout(due.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(HealthEvent::Checks(new)) => {
+                let now = Instant::now();
+                let mut old = std::mem::take(&mut checks);
+                for (key, check) in new {
+                    let due = match old.remove(&key) {
+                        Some((prev, due)) if prev == check => due,
+                        _ => {
+                            results.lock().unwrap().remove(&key);
+                            Some(now)
+                        }
+                    };
+                    checks.insert(key, (check, due));
+                }
+                results
+                    .lock()
+                    .unwrap()
+                    .retain(|key, _| checks.contains_key(key));
+            }
+            Ok(HealthEvent::Checked(key, check, status, latency_ms)) => {
+                // Drop results for checks removed or changed meanwhile.
+                if let Some((current, due)) = checks.get_mut(&key).filter(|(c, _)| *c == check) {
+                    let mut results = results.lock().unwrap();
+                    let has_passed = results.get(&key).is_some_and(|h| h.has_passed);
+                    let mut health = Health {
+                        status,
+                        latency_ms,
+                        has_passed,
+                    };
+                    health.has_passed |= health.ok();
+                    results.insert(key, health);
+                    *due = Some(Instant::now() + current.interval());
+                }
+            }
+            Ok(HealthEvent::Stop) | Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+        }
+
+        let now = Instant::now();
+        for (key, (check, due)) in checks.iter_mut() {
+            if due.is_none_or(|due| due > now) {
+                continue;
+            }
+            *due = None;
+            let (key, check, tx) = (key.clone(), check.clone(), tx.clone());
+            thread::spawn(move || {
+                let started = Instant::now();
+                let status = http_get(&check.url, check.timeout()).ok().map(|r| r.status);
+                let latency_ms = started.elapsed().as_millis() as u64;
+                let _ = tx.send(HealthEvent::Checked(key, check, status, latency_ms));
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn parses_probe_specs() {
+        assert_eq!(Probe::parse("tcp", Some(3000)), Ok(Probe::Tcp(3000)));
+        assert_eq!(Probe::parse("tcp:5173", None), Ok(Probe::Tcp(5173)));
+        assert!(Probe::parse("tcp", None).is_err());
+        assert!(Probe::parse("https://x", None).is_err());
+        assert!(Probe::parse("http://127.0.0.1:abc/", None).is_err());
+        let http = Probe::parse("http://[::1]:8080/health", None).unwrap();
+        assert_eq!(http.to_string(), "http://[::1]:8080/health");
+    }
+
+    #[test]
+    fn http_get_reads_status() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // Serve every connection: a parallel test probing a just-freed
+        // port may land here first.
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buf = [0u8; 256];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(b"HTTP/1.1 204 No Content\r\n\r\n");
+            }
+        });
+        let url = format!("http://127.0.0.1:{port}/health");
+        let resp = http_get(&url, Duration::from_secs(2)).unwrap();
+        assert_eq!(resp.status, 204);
+    }
+
+    #[test]
+    fn client_errors_are_neither_ready_nor_healthy() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buf = [0u8; 256];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\n\r\n");
+            }
+        });
+        let url = format!("http://127.0.0.1:{port}/health");
+        assert!(!Probe::Http(url).check(Duration::from_secs(2)));
+        let health = Health {
+            status: Some(404),
+            latency_ms: 1,
+            has_passed: false,
+        };
+        assert!(!health.ok());
+        assert!(status_ok(204) && status_ok(302) && !status_ok(401));
+    }
+
+    #[test]
+    fn tcp_listening_sees_bound_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(tcp_listening(port, Duration::from_millis(500)));
+        drop(listener);
+        assert!(!tcp_listening(port, Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn port_prober_fills_in_listening() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let tmp = tempfile::tempdir().unwrap();
+        let mut meta = crate::model::Meta::new(tmp.path().to_path_buf(), std::process::id());
+        meta.ports.insert("server".into(), port);
+        std::fs::write(tmp.path().join("ab12"), meta.to_string()).unwrap();
+
+        let mut prober = PortProber::spawn();
+        let mut envs = crate::model::load_all(tmp.path());
+        prober.apply(&mut envs);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while envs[0].listening_for("server").is_none() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+            prober.apply(&mut envs);
+        }
+        assert_eq!(envs[0].listening_for("server"), Some(true));
+    }
+
+    #[test]
+    fn finds_shared_and_foreign_ports() {
+        let mut sleeper = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let mut a = crate::model::Meta::new("/code/client-a/app".into(), std::process::id());
+        a.ports.insert("server".into(), 3000);
+        a.ports.insert("admin".into(), 4000);
+        let mut b = crate::model::Meta::new("/code/client-b/app".into(), sleeper.id());
+        b.ports.insert("server".into(), 3000);
+        std::fs::write(tmp.path().join("a1"), a.to_string()).unwrap();
+        std::fs::write(tmp.path().join("b2"), b.to_string()).unwrap();
+        let mut envs = crate::model::load_all(tmp.path());
+
+        let owner = |pid: u32, name: &str, lineage: &[u32]| SocketOwner {
+            pid,
+            name: Some(name.into()),
+            lineage: lineage.to_vec(),
+        };
+        let owners = HashMap::from([
+            (
+                3000,
+                vec![owner(99999, "node", &[99999, std::process::id(), 1])],
+            ),
+            (4000, vec![owner(77777, "nginx", &[77777, 1])]),
+        ]);
+        find_conflicts(&mut envs, &owners);
+        sleeper.kill().unwrap();
+        sleeper.wait().unwrap();
+
+        let (a, b) = (&envs[0], &envs[1]);
+        // a's own process has the port, so it only shares the declaration.
+        assert_eq!(
+            a.port_conflict_for("server").unwrap().describe(),
+            ":3000 also declared by client-b/app"
+        );
+        assert_eq!(
+            b.port_conflict_for("server").unwrap().describe(),
+            ":3000 also declared by client-a/app; bound by client-a/app (pid 99999)"
+        );
+        assert_eq!(
+            a.port_conflict_for("admin").unwrap().describe(),
+            ":4000 bound by pid 77777 (nginx)"
+        );
+    }
+
+    #[test]
+    fn health_prober_synthesizes_missing_states() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buf = [0u8; 256];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n");
+            }
+        });
+        let tmp = tempfile::tempdir().unwrap();
+        let mut meta = crate::model::Meta::new(tmp.path().to_path_buf(), std::process::id());
+        for unit in ["api", "server"] {
+            meta.health_checks.insert(
+                unit.into(),
+                HealthCheck {
+                    url: format!("http://127.0.0.1:{port}/health"),
+                    interval: None,
+                    timeout: None,
+                },
+            );
+        }
+        std::fs::write(tmp.path().join("ab12"), meta.to_string()).unwrap();
+        std::fs::write(tmp.path().join("ab12.server.status"), "building").unwrap();
+
+        let mut prober = HealthProber::spawn();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let envs = loop {
+            let mut envs = crate::model::load_all(tmp.path());
+            prober.apply(&mut envs);
+            if envs[0].health.len() == 2 || Instant::now() > deadline {
+                break envs;
+            }
+            thread::sleep(Duration::from_millis(20));
+        };
+        assert!(envs[0].health_for("api").unwrap().ok());
+        let states: Vec<(&str, &State)> = envs[0]
+            .units
+            .iter()
+            .map(|u| (u.name.as_str(), &u.state))
+            .collect();
+        // The writer's state wins; the unit it doesn't publish is synthetic.
+        assert_eq!(
+            states,
+            [("api", &State::Ready), ("server", &State::Building)]
+        );
+    }
+
+    #[test]
+    fn slow_health_checks_do_not_block_others() {
+        // Accepts but never answers, so its check takes the full timeout.
+        let silent = TcpListener::bind("127.0.0.1:0").unwrap();
+        let silent_port = silent.local_addr().unwrap().port();
+        let answering = TcpListener::bind("127.0.0.1:0").unwrap();
+        let answering_port = answering.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for mut stream in answering.incoming().flatten() {
+                let mut buf = [0u8; 256];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n");
+            }
+        });
+        let check = |port: u16| HealthCheck {
+            url: format!("http://127.0.0.1:{port}/health"),
+            interval: None,
+            timeout: Some(Duration::from_secs(30)),
+        };
+        let tmp = tempfile::tempdir().unwrap();
+        let mut meta = crate::model::Meta::new(tmp.path().to_path_buf(), std::process::id());
+        meta.health_checks
+            .insert("api".into(), check(answering_port));
+        meta.health_checks.insert("hung".into(), check(silent_port));
+        std::fs::write(tmp.path().join("ab12"), meta.to_string()).unwrap();
+
+        let mut prober = HealthProber::spawn();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let envs = loop {
+            let mut envs = crate::model::load_all(tmp.path());
+            prober.apply(&mut envs);
+            if !envs[0].health.is_empty() || Instant::now() > deadline {
+                break envs;
+            }
+            thread::sleep(Duration::from_millis(20));
+        };
+        assert!(envs[0].health_for("api").unwrap().ok());
+        assert!(envs[0].health_for("hung").is_none());
+        drop(silent);
+    }
+}
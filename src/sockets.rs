@@ -0,0 +1,201 @@
+use std::collections::{BTreeSet, HashMap};
+
+/// A process with a listening TCP socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketOwner {
+    pub pid: u32,
+    /// Short command name, e.g. `node`.
+    pub name: Option<String>,
+    /// `pid`, then its parent, grandparent and so on up to init, so the
+    /// socket can be traced back to whichever environment started it.
+    pub lineage: Vec<u32>,
+}
+
+/// Finds the processes listening on a set of ports, remembering which
+/// process owns each socket.
+///
+/// The `LISTEN` sockets are read from `/proc/net/tcp{,6}` on every call,
+/// but the walk over every `/proc/<pid>/fd` that maps a socket to its
+/// owner only happens when that set of sockets changes. Always empty
+/// outside Linux.
+#[derive(Debug, Default)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub struct ListenerCache {
+    /// Inode and port of each `LISTEN` socket on a port last asked about.
+    inodes: HashMap<u64, u16>,
+    owners: HashMap<u64, Vec<SocketOwner>>,
+}
+
+impl ListenerCache {
+    /// The processes listening on each of `ports`, on any address. Ports
+    /// with no listener, or whose owner isn't visible to us (another
+    /// user's process), are left out.
+    #[cfg(target_os = "linux")]
+    pub fn listeners(&mut self, ports: &BTreeSet<u16>) -> HashMap<u16, Vec<SocketOwner>> {
+        let mut inodes: HashMap<u64, u16> = HashMap::new();
+        for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+            let Ok(contents) = std::fs::read_to_string(table) else {
+                continue;
+            };
+            inodes.extend(
+                parse_listening(&contents)
+                    .into_iter()
+                    .filter(|(_, port)| ports.contains(port)),
+            );
+        }
+        if inodes != self.inodes {
+            self.owners = socket_owners(&inodes);
+            self.inodes = inodes;
+        }
+
+        let mut out: HashMap<u16, Vec<SocketOwner>> = HashMap::new();
+        for (inode, port) in &self.inodes {
+            let listeners = out.entry(*port).or_default();
+            for owner in self.owners.get(inode).into_iter().flatten() {
+                // IPv4 and IPv6 sockets on one port share their owner.
+                if !listeners.iter().any(|o| o.pid == owner.pid) {
+                    listeners.push(owner.clone());
+                }
+            }
+        }
+        out.retain(|_, owners| !owners.is_empty());
+        for owners in out.values_mut() {
+            owners.sort_by_key(|o| o.pid);
+        }
+        out
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn listeners(&mut self, _ports: &BTreeSet<u16>) -> HashMap<u16, Vec<SocketOwner>> {
+        HashMap::new()
+    }
+}
+
+/// The processes holding each of `inodes` open, found by walking every
+/// `/proc/<pid>/fd`.
+#[cfg(target_os = "linux")]
+fn socket_owners(inodes: &HashMap<u64, u16>) -> HashMap<u64, Vec<SocketOwner>> {
+    let mut out: HashMap<u64, Vec<SocketOwner>> = HashMap::new();
+    if inodes.is_empty() {
+        return out;
+    }
+    let Ok(procs) = std::fs::read_dir("/proc") else {
+        return out;
+    };
+    for entry in procs.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let mut found: BTreeSet<u64> = BTreeSet::new();
+        for fd in fds.flatten() {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            let inode = target
+                .to_str()
+                .and_then(|t| t.strip_prefix("socket:["))
+                .and_then(|t| t.strip_suffix(']'))
+                .and_then(|t| t.parse::<u64>().ok());
+            if let Some(inode) = inode.filter(|i| inodes.contains_key(i)) {
+                found.insert(inode);
+            }
+        }
+        if found.is_empty() {
+            continue;
+        }
+        let owner = SocketOwner {
+            pid,
+            name: stat(pid).map(|(name, _)| name),
+            lineage: lineage(pid),
+        };
+        for inode in found {
+            out.entry(inode).or_default().push(owner.clone());
+        }
+    }
+    out
+}
+
+/// `(inode, port)` for each socket in the `LISTEN` state (`0A`) in a
+/// `/proc/net/tcp` or `tcp6` table.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_listening(table: &str) -> Vec<(u64, u16)> {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.get(3) != Some(&"0A") {
+                return None;
+            }
+            let (_, port) = fields.get(1)?.rsplit_once(':')?;
+            let port = u16::from_str_radix(port, 16).ok()?;
+            let inode = fields.get(9)?.parse().ok()?;
+            Some((inode, port))
+        })
+        .collect()
+}
+
+/// `comm` and parent PID from `/proc/<pid>/stat`.
+#[cfg(target_os = "linux")]
+fn stat(pid: u32) -> Option<(String, u32)> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let name = stat.get(open + 1..close)?.to_string();
+    // The fields after `comm` start at field 3; the parent is field 4.
+    let ppid = stat[close + 1..].split_whitespace().nth(1)?.parse().ok()?;
+    Some((name, ppid))
+}
+
+#[cfg(target_os = "linux")]
+fn lineage(pid: u32) -> Vec<u32> {
+    let mut out = vec![pid];
+    let mut current = pid;
+    while let Some((_, parent)) = stat(current) {
+        if parent == 0 || out.contains(&parent) {
+            break;
+        }
+        out.push(parent);
+        current = parent;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_listening_sockets() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0BB8 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4242 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:0BB8 0100007F:C350 01 00000000:00000000 00:00000000 00000000  1000        0 4243 1 0000000000000000 20 4 30 10 -1
+   2: 00000000000000000000000001000000:1435 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4244 1 0000000000000000 100 0 0 10 0
+";
+        assert_eq!(parse_listening(table), [(4242, 3000), (4244, 5173)]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn finds_our_own_listener() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut cache = ListenerCache::default();
+        let owners = cache.listeners(&BTreeSet::from([port]));
+        let owner = &owners[&port][0];
+        assert_eq!(owner.pid, std::process::id());
+        assert_eq!(owner.lineage[0], std::process::id());
+        assert!(owner.lineage.len() > 1);
+        // Served from the cache while the socket stays, gone with it.
+        assert_eq!(cache.listeners(&BTreeSet::from([port])), owners);
+        drop(listener);
+        assert!(cache.listeners(&BTreeSet::from([port])).is_empty());
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::journal::Entry;
+use crate::model::{State, format_duration};
+
+/// Samples kept per unit and phase; older ones age out so a slowdown
+/// that sticks becomes the new normal.
+pub const WINDOW: usize = 50;
+
+/// Samples needed before a slow run is called a regression.
+pub const MIN_SAMPLES: usize = 5;
+
+/// A run this many times the median is a regression...
+pub const REGRESSION_FACTOR: f64 = 2.0;
+
+/// ...as long as it's also this much slower in absolute terms, so a
+/// 1s → 2s blip doesn't count.
+pub const MIN_REGRESSION_SECS: u64 = 5;
+
+/// What was being timed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    /// `building` until `ready` or `running`.
+    Build,
+    /// `starting` until `ready`.
+    Startup,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Phase::Build => "build",
+            Phase::Startup => "startup",
+        })
+    }
+}
+
+/// Duration statistics for one unit and phase, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Summary {
+    pub phase: Phase,
+    pub count: usize,
+    pub median: u64,
+    pub p90: u64,
+    pub last: u64,
+}
+
+impl Summary {
+    fn new(phase: Phase, samples: &VecDeque<u64>) -> Option<Summary> {
+        let last = *samples.back()?;
+        let mut sorted: Vec<u64> = samples.iter().copied().collect();
+        sorted.sort_unstable();
+        let n = sorted.len();
+        let median = if n % 2 == 0 {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+        } else {
+            sorted[n / 2]
+        };
+        // Nearest rank.
+        let p90 = sorted[(n * 9).div_ceil(10) - 1];
+        Some(Summary {
+            phase,
+            count: n,
+            median,
+            p90,
+            last,
+        })
+    }
+
+    /// Compact label for a unit row: `build ~12s`, or with the last run
+    /// when it regressed, `build 30s, 3.0× median`.
+    pub fn label(&self) -> String {
+        match self.regression() {
+            Some(ratio) => format!(
+                "{} {}, {ratio:.1}\u{d7} median",
+                self.phase,
+                format_duration(self.last)
+            ),
+            None => format!("{} ~{}", self.phase, format_duration(self.median)),
+        }
+    }
+
+    /// How many times slower than the median the last run was, if that
+    /// counts as a regression.
+    pub fn regression(&self) -> Option<f64> {
+        if self.count < MIN_SAMPLES || self.last < self.median + MIN_REGRESSION_SECS {
+            return None;
+        }
+        let ratio = self.last as f64 / self.median.max(1) as f64;
+        (ratio >= REGRESSION_FACTOR).then_some(ratio)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Track {
+    building_since: Option<u64>,
+    starting_since: Option<u64>,
+    build: VecDeque<u64>,
+    startup: VecDeque<u64>,
+    /// Phase of the most recent sample.
+    latest: Option<Phase>,
+}
+
+impl Track {
+    fn samples(&self, phase: Phase) -> &VecDeque<u64> {
+        match phase {
+            Phase::Build => &self.build,
+            Phase::Startup => &self.startup,
+        }
+    }
+
+    fn push(&mut self, phase: Phase, secs: u64) {
+        let samples = match phase {
+            Phase::Build => &mut self.build,
+            Phase::Startup => &mut self.startup,
+        };
+        if samples.len() == WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(secs);
+        self.latest = Some(phase);
+    }
+}
+
+/// Build and startup durations per (project dir, unit), derived from
+/// journal transitions. Keyed by directory rather than environment ID so
+/// history carries across sessions.
+#[derive(Debug, Default)]
+pub struct Stats {
+    units: HashMap<(PathBuf, String), Track>,
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats::default()
+    }
+
+    /// Replay `entries`, oldest first.
+    pub fn from_entries(entries: &[Entry]) -> Stats {
+        let mut stats = Stats::new();
+        for entry in entries {
+            stats.record(entry);
+        }
+        stats
+    }
+
+    /// Feed one transition. Returns the phases it completed; a `ready`
+    /// can finish both a build and the startup around it.
+    pub fn record(&mut self, entry: &Entry) -> Vec<Phase> {
+        let track = self
+            .units
+            .entry((entry.dir.clone(), entry.unit.clone()))
+            .or_default();
+        let mut done = Vec::new();
+        match entry.new {
+            State::Starting => {
+                track.starting_since = Some(entry.ts);
+                track.building_since = None;
+            }
+            State::Building => {
+                // A rebuild of a unit that was already up isn't startup.
+                if matches!(entry.old, Some(State::Ready | State::Running)) {
+                    track.starting_since = None;
+                }
+                track.building_since = Some(entry.ts);
+            }
+            State::Ready | State::Running => {
+                if let Some(since) = track.building_since.take() {
+                    track.push(Phase::Build, entry.ts.saturating_sub(since));
+                    done.push(Phase::Build);
+                }
+                if entry.new == State::Ready {
+                    if let Some(since) = track.starting_since.take() {
+                        track.push(Phase::Startup, entry.ts.saturating_sub(since));
+                        done.push(Phase::Startup);
+                    }
+                }
+            }
+            _ => {
+                track.building_since = None;
+                track.starting_since = None;
+            }
+        }
+        done
+    }
+
+    pub fn summary(&self, dir: &Path, unit: &str, phase: Phase) -> Option<Summary> {
+        let track = self.units.get(&(dir.to_path_buf(), unit.to_string()))?;
+        Summary::new(phase, track.samples(phase))
+    }
+
+    /// The summary for whichever phase the unit last completed, for a
+    /// one-glance display next to the unit.
+    pub fn latest(&self, dir: &Path, unit: &str) -> Option<Summary> {
+        let track = self.units.get(&(dir.to_path_buf(), unit.to_string()))?;
+        let phase = track.latest?;
+        Summary::new(phase, track.samples(phase))
+    }
+
+    /// Every summary, sorted by directory, unit and phase.
+    pub fn all(&self) -> Vec<(&Path, &str, Summary)> {
+        let mut out: Vec<_> = self
+            .units
+            .iter()
+            .flat_map(|((dir, unit), track)| {
+                [Phase::Build, Phase::Startup]
+                    .into_iter()
+                    .filter_map(|phase| Summary::new(phase, track.samples(phase)))
+                    .map(move |s| (dir.as_path(), unit.as_str(), s))
+            })
+            .collect();
+        out.sort_by(|a, b| (a.0, a.1, a.2.phase).cmp(&(b.0, b.1, b.2.phase)));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(ts: u64, old: Option<State>, new: State) -> Entry {
+        Entry {
+            ts,
+            env: "ab12".into(),
+            dir: PathBuf::from("/code/app"),
+            unit: "vite".into(),
+            old,
+            new,
+            detail: None,
+            since: None,
+        }
+    }
+
+    #[test]
+    fn times_builds_and_startups() {
+        let stats = Stats::from_entries(&[
+            entry(0, None, State::Starting),
+            entry(2, Some(State::Starting), State::Building),
+            entry(10, Some(State::Building), State::Running),
+            entry(12, Some(State::Running), State::Ready),
+            // HMR rebuild: a build sample, but not a startup.
+            entry(100, Some(State::Ready), State::Building),
+            entry(104, Some(State::Building), State::Ready),
+            // A failed build is not a sample.
+            entry(200, Some(State::Ready), State::Building),
+            entry(230, Some(State::Building), State::Failed),
+            entry(240, Some(State::Failed), State::Ready),
+        ]);
+        let dir = Path::new("/code/app");
+        let build = stats.summary(dir, "vite", Phase::Build).unwrap();
+        assert_eq!((build.count, build.last), (2, 4));
+        let startup = stats.summary(dir, "vite", Phase::Startup).unwrap();
+        assert_eq!((startup.count, startup.last), (1, 12));
+        assert_eq!(stats.latest(dir, "vite").unwrap().phase, Phase::Build);
+    }
+
+    #[test]
+    fn flags_regressions_against_the_median() {
+        let summary = |samples: &[u64]| {
+            Summary::new(Phase::Build, &samples.iter().copied().collect()).unwrap()
+        };
+        let s = summary(&[10, 12, 11, 9, 10, 30]);
+        assert_eq!((s.median, s.p90), (10, 30));
+        assert_eq!(s.regression(), Some(3.0));
+        // Too few samples, or too small in absolute terms.
+        assert_eq!(summary(&[10, 10, 30]).regression(), None);
+        assert_eq!(summary(&[1, 1, 1, 1, 1, 4]).regression(), None);
+        assert_eq!(summary(&[10, 12, 11, 9, 10, 12]).regression(), None);
+    }
+}
@@ -0,0 +1,655 @@
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+
+use crate::model::{Environment, Meta, State, UnitStatus, now_secs};
+use crate::patterns::{Rule, match_line};
+use crate::probe::Probe;
+use crate::writer::{self, EnvironmentHandle};
+
+/// How long children get to exit after a forwarded signal before they
+/// are sent SIGKILL.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// How long a `failed` status stays published before cleanup removes it,
+/// so dashboards get a chance to observe (and announce) the failure.
+const FAILED_STATUS_LINGER: Duration = Duration::from_secs(1);
+
+/// How often a readiness probe is retried, and how long each try may take.
+const PROBE_INTERVAL: Duration = Duration::from_millis(250);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Environment variables exported to supervised commands so nested tools
+/// (`sutra status set`, the INTEGRATION.md helpers) publish into the same
+/// environment.
+pub const ENV_ID_VAR: &str = "SUTRA_ENV_ID";
+pub const STATE_DIR_VAR: &str = "SUTRA_STATE_DIR";
+pub const UNIT_VAR: &str = "SUTRA_UNIT";
+
+/// This process's claim on an environment in the registry.
+///
+/// `Owned` means we wrote the meta file and remove the whole environment
+/// on drop. `Joined` means another live supervisor owns the meta file and
+/// we only publish (and on drop, remove) the units we wrote.
+pub enum Registration {
+    Owned(Box<EnvironmentHandle>),
+    Joined {
+        state_dir: PathBuf,
+        id: String,
+        units: HashSet<String>,
+    },
+}
+
+impl Registration {
+    /// Register `meta` under `id`, or join the environment already
+    /// registered there if its supervisor is alive. When joining, any
+    /// ports in `meta` are merged into the existing meta file.
+    pub fn attach(state_dir: &Path, id: &str, meta: Meta) -> io::Result<Registration> {
+        let meta_path = state_dir.join(id);
+        if let Some(existing) = Environment::load(&meta_path) {
+            if existing.alive && existing.pid != std::process::id() {
+                if !meta.ports.is_empty() {
+                    if let Some(mut merged) = std::fs::read_to_string(&meta_path)
+                        .ok()
+                        .and_then(|c| Meta::parse(&c))
+                    {
+                        merged.ports.extend(meta.ports);
+                        writer::write_meta(state_dir, id, &merged)?;
+                    }
+                }
+                return Ok(Registration::Joined {
+                    state_dir: state_dir.to_path_buf(),
+                    id: id.to_string(),
+                    units: HashSet::new(),
+                });
+            }
+        }
+        // Stale leftovers from a crashed run would otherwise show up as
+        // rows in our card.
+        writer::remove_status_files(state_dir, id)?;
+        let mut meta = meta;
+        meta.started.get_or_insert_with(now_secs);
+        Ok(Registration::Owned(Box::new(EnvironmentHandle::register(
+            state_dir, id, meta,
+        )?)))
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            Registration::Owned(handle) => handle.id(),
+            Registration::Joined { id, .. } => id,
+        }
+    }
+
+    pub fn state_dir(&self) -> &Path {
+        match self {
+            Registration::Owned(handle) => handle.state_dir(),
+            Registration::Joined { state_dir, .. } => state_dir,
+        }
+    }
+
+    pub fn set_status(&mut self, unit: &str, state: State, detail: Option<&str>) -> io::Result<()> {
+        self.publish(&UnitStatus::new(unit, state, detail))
+    }
+
+    /// Write a unit's full status, v2 fields included.
+    pub fn publish(&mut self, status: &UnitStatus) -> io::Result<()> {
+        match self {
+            Registration::Owned(handle) => handle.publish(status),
+            Registration::Joined {
+                state_dir,
+                id,
+                units,
+            } => {
+                units.insert(status.name.clone());
+                writer::write_status(state_dir, id, status)
+            }
+        }
+    }
+
+    /// Variables to export to a supervised command for `unit`. Includes
+    /// `REGISTRY_DIR`/`REGISTRY_KEY`, the names the INTEGRATION.md helpers
+    /// read.
+    pub fn child_env(&self, unit: &str) -> Vec<(&'static str, String)> {
+        let dir = self.state_dir().display().to_string();
+        vec![
+            (ENV_ID_VAR, self.id().to_string()),
+            (STATE_DIR_VAR, dir.clone()),
+            (UNIT_VAR, unit.to_string()),
+            ("REGISTRY_DIR", dir),
+            ("REGISTRY_KEY", self.id().to_string()),
+        ]
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Registration::Joined {
+            state_dir,
+            id,
+            units,
+        } = self
+        {
+            for unit in units.iter() {
+                let _ = std::fs::remove_file(writer::status_path(state_dir, id, unit));
+            }
+        }
+        // Owned: EnvironmentHandle's own Drop removes everything.
+    }
+}
+
+/// A command to supervise as one unit.
+pub struct UnitSpec {
+    pub name: String,
+    /// Program and arguments.
+    pub argv: Vec<String>,
+    /// Working directory; inherited when `None`.
+    pub dir: Option<PathBuf>,
+    /// Extra environment variables, on top of [`Registration::child_env`].
+    pub env: Vec<(String, String)>,
+    /// Promotes the unit from `running` to `ready` once it passes.
+    pub ready: Option<Probe>,
+    /// Output patterns that set the unit's state. When non-empty the
+    /// child's stdout/stderr are piped through the supervisor.
+    pub rules: Vec<Rule>,
+}
+
+impl UnitSpec {
+    pub fn new(name: &str, argv: Vec<String>) -> Self {
+        UnitSpec {
+            name: name.to_string(),
+            argv,
+            dir: None,
+            env: Vec::new(),
+            ready: None,
+            rules: Vec::new(),
+        }
+    }
+
+    /// A unit running `command` through `sh -c`, as Procfile entries do.
+    pub fn shell(name: &str, command: &str) -> Self {
+        UnitSpec::new(name, vec!["sh".into(), "-c".into(), command.into()])
+    }
+}
+
+/// How supervised children share the supervisor's terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Attach {
+    /// Inherit stdio and the process group, so the child can be
+    /// interactive (`sutra run`).
+    Inherit,
+    /// Give each child its own process group, so signals reach its whole
+    /// tree, and prefix its output with the unit name (`sutra up`).
+    Prefixed,
+}
+
+/// What the supervisor loop reacts to.
+enum Event {
+    Signal(i32),
+    Exited(usize, ExitStatus),
+    Ready(usize),
+    /// An output line matched one of the unit's [`Rule`]s.
+    Matched(usize, State, String),
+}
+
+/// A spawned unit, tracked by the supervisor loop.
+struct Supervised {
+    spec: UnitSpec,
+    pid: u32,
+    exited: Option<ExitStatus>,
+    /// Tells the readiness probe thread to give up.
+    stop_probe: Arc<AtomicBool>,
+}
+
+/// Run `unit` to completion, publishing `starting`, `running` and a final
+/// `stopped` / `failed: …` status through `reg`. SIGTERM and SIGHUP are
+/// forwarded to the child; SIGINT isn't, as the child is in our process
+/// group and gets a terminal's Ctrl-C directly.
+///
+/// Returns the exit code to propagate: the child's own code, or
+/// `128 + signal` if it was killed by a signal.
+pub fn run(reg: &mut Registration, unit: UnitSpec) -> io::Result<i32> {
+    let (units, _) = supervise(reg, vec![unit], Attach::Inherit)?;
+    Ok(units[0].exited.map_or(1, exit_code))
+}
+
+/// Run every unit until all of them have exited or the supervisor is
+/// signalled, in which case the signal is forwarded to each unit's
+/// process group. Units that exit on their own are marked `stopped` or
+/// `failed` while the rest keep running.
+///
+/// Returns 0 after a signalled teardown, 1 if any unit failed on its own,
+/// and 0 otherwise.
+pub fn run_all(reg: &mut Registration, units: Vec<UnitSpec>) -> io::Result<i32> {
+    let (units, shutting_down) = supervise(reg, units, Attach::Prefixed)?;
+    let any_failed = units.iter().any(|u| u.exited.is_some_and(|s| !s.success()));
+    Ok(if any_failed && !shutting_down { 1 } else { 0 })
+}
+
+/// The shared event loop: spawn, publish, forward signals, escalate to
+/// SIGKILL after [`SHUTDOWN_GRACE`], and publish final states. Returns
+/// the units with their exit statuses and whether a signal started the
+/// shutdown.
+fn supervise(
+    reg: &mut Registration,
+    specs: Vec<UnitSpec>,
+    attach: Attach,
+) -> io::Result<(Vec<Supervised>, bool)> {
+    let (tx, rx) = mpsc::channel();
+
+    let mut signals = Signals::new([SIGTERM, SIGINT, SIGHUP])?;
+    let signals_handle = signals.handle();
+    {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for sig in signals.forever() {
+                if tx.send(Event::Signal(sig)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    let prefix_width = specs.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    let mut units: Vec<Supervised> = Vec::with_capacity(specs.len());
+    for spec in specs {
+        publish(reg, &spec.name, State::Starting, None, None);
+        let index = units.len();
+        let child = match spawn(reg, &spec, attach, prefix_width, index, &tx) {
+            Ok(child) => child,
+            Err(e) => {
+                let detail = format!("could not start {}: {e}", spec.argv[0]);
+                publish(reg, &spec.name, State::Failed, Some(&detail), None);
+                for unit in &units {
+                    let _ = signal_unit(unit.pid, attach, Signal::SIGTERM);
+                }
+                thread::sleep(FAILED_STATUS_LINGER);
+                signals_handle.close();
+                return Err(e);
+            }
+        };
+        let pid = child.id();
+        publish(reg, &spec.name, State::Running, None, Some(pid));
+        wait_in_background(child, index, tx.clone());
+
+        let stop_probe = Arc::new(AtomicBool::new(false));
+        if let Some(probe) = spec.ready.clone() {
+            probe_in_background(probe, index, stop_probe.clone(), tx.clone());
+        }
+        units.push(Supervised {
+            spec,
+            pid,
+            exited: None,
+            stop_probe,
+        });
+    }
+
+    let mut shutting_down = false;
+    let mut kill_deadline: Option<Instant> = None;
+    while units.iter().any(|u| u.exited.is_none()) {
+        let event = match kill_deadline {
+            Some(deadline) => {
+                match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(event) => event,
+                    Err(_) => {
+                        for unit in units.iter().filter(|u| u.exited.is_none()) {
+                            let _ = signal_unit(unit.pid, attach, Signal::SIGKILL);
+                        }
+                        kill_deadline = None;
+                        continue;
+                    }
+                }
+            }
+            None => match rx.recv() {
+                Ok(event) => event,
+                Err(_) => return Err(io::Error::other("supervisor channel closed")),
+            },
+        };
+        match event {
+            Event::Signal(sig) => {
+                shutting_down = true;
+                kill_deadline.get_or_insert_with(|| Instant::now() + SHUTDOWN_GRACE);
+                // An inheriting child shares our process group, so a
+                // terminal's Ctrl-C has already reached it. A second
+                // SIGINT makes many dev servers force-quit, skipping
+                // their cleanup.
+                let delivered = attach == Attach::Inherit && sig == SIGINT;
+                if let Some(sig) = Signal::try_from(sig).ok().filter(|_| !delivered) {
+                    for unit in units.iter().filter(|u| u.exited.is_none()) {
+                        let _ = signal_unit(unit.pid, attach, sig);
+                    }
+                }
+            }
+            Event::Ready(index) => {
+                let unit = &units[index];
+                if unit.exited.is_none() && !shutting_down {
+                    publish(reg, &unit.spec.name, State::Ready, None, Some(unit.pid));
+                }
+            }
+            Event::Matched(index, state, line) => {
+                let unit = &units[index];
+                if unit.exited.is_none() && !shutting_down {
+                    publish(reg, &unit.spec.name, state, Some(&line), Some(unit.pid));
+                }
+            }
+            Event::Exited(index, status) => {
+                let unit = &mut units[index];
+                unit.exited = Some(status);
+                unit.stop_probe.store(true, Ordering::Relaxed);
+                let (state, detail) = final_status(status, shutting_down);
+                if attach == Attach::Prefixed && !shutting_down {
+                    eprintln!(
+                        "{:>prefix_width$} | {}",
+                        unit.spec.name,
+                        detail.as_deref().unwrap_or("exited")
+                    );
+                }
+                publish(reg, &unit.spec.name, state, detail.as_deref(), None);
+            }
+        }
+    }
+    signals_handle.close();
+
+    if !shutting_down && units.iter().any(|u| u.exited.is_some_and(|s| !s.success())) {
+        thread::sleep(FAILED_STATUS_LINGER);
+    }
+    Ok((units, shutting_down))
+}
+
+/// Publish a unit's status, with `PID` set while its child is running so
+/// dashboards notice if it dies without a final status. A failed write is
+/// reported instead of returned: bailing out of [`supervise`] would drop
+/// the registration while the children keep running, unsupervised and
+/// unlisted.
+fn publish(
+    reg: &mut Registration,
+    unit: &str,
+    state: State,
+    detail: Option<&str>,
+    pid: Option<u32>,
+) {
+    let mut status = UnitStatus::new(unit, state, detail);
+    status.pid = pid;
+    if let Err(e) = reg.publish(&status) {
+        eprintln!("sutra: could not publish status for {unit}: {e}");
+    }
+}
+
+fn spawn(
+    reg: &Registration,
+    unit: &UnitSpec,
+    attach: Attach,
+    prefix_width: usize,
+    index: usize,
+    tx: &mpsc::Sender<Event>,
+) -> io::Result<Child> {
+    let (program, args) = unit
+        .argv
+        .split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .envs(reg.child_env(&unit.name))
+        .envs(unit.env.iter().map(|(k, v)| (k, v)));
+    if let Some(dir) = &unit.dir {
+        cmd.current_dir(dir);
+    }
+    if attach == Attach::Prefixed {
+        cmd.process_group(0).stdin(Stdio::null());
+    }
+    let piped = attach == Attach::Prefixed || !unit.rules.is_empty();
+    if piped {
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    let mut child = cmd.spawn()?;
+    if piped {
+        let prefix = match attach {
+            Attach::Prefixed => format!("{:>prefix_width$} | ", unit.name),
+            Attach::Inherit => String::new(),
+        };
+        let tap = Tap {
+            rules: Arc::new(unit.rules.clone()),
+            index,
+            tx: tx.clone(),
+        };
+        if let Some(out) = child.stdout.take() {
+            copy_output(out, io::stdout(), prefix.clone(), tap.clone());
+        }
+        if let Some(err) = child.stderr.take() {
+            copy_output(err, io::stderr(), prefix, tap);
+        }
+    }
+    Ok(child)
+}
+
+/// Where a piped unit's output lines are matched and reported.
+#[derive(Clone)]
+struct Tap {
+    rules: Arc<Vec<Rule>>,
+    index: usize,
+    tx: mpsc::Sender<Event>,
+}
+
+/// Pass `src` through to `dst` line by line, each line prefixed, and
+/// report lines matching the unit's rules. Lines are read as bytes so
+/// non-UTF-8 output is forwarded untouched.
+fn copy_output<R, W>(src: R, dst: W, prefix: String, tap: Tap)
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut src = BufReader::new(src);
+        let mut dst = dst;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match src.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if !line.ends_with(b"\n") {
+                        line.push(b'\n');
+                    }
+                    let mut buf = prefix.clone().into_bytes();
+                    buf.extend_from_slice(&line);
+                    let _ = dst.write_all(&buf);
+                    let _ = dst.flush();
+
+                    if !tap.rules.is_empty() {
+                        let text = String::from_utf8_lossy(&line);
+                        if let Some((state, detail)) = match_line(&tap.rules, &text) {
+                            let _ = tap.tx.send(Event::Matched(tap.index, state, detail));
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn wait_in_background(mut child: Child, index: usize, tx: mpsc::Sender<Event>) {
+    thread::spawn(move || {
+        if let Ok(status) = child.wait() {
+            let _ = tx.send(Event::Exited(index, status));
+        }
+    });
+}
+
+/// Poll `probe` until it passes (reporting [`Event::Ready`]) or `stop`
+/// is set because the unit exited.
+fn probe_in_background(probe: Probe, index: usize, stop: Arc<AtomicBool>, tx: mpsc::Sender<Event>) {
+    thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            if probe.check(PROBE_TIMEOUT) {
+                let _ = tx.send(Event::Ready(index));
+                return;
+            }
+            thread::sleep(PROBE_INTERVAL);
+        }
+    });
+}
+
+/// Signal a unit: just its PID when it shares our process group, its
+/// whole group otherwise.
+fn signal_unit(pid: u32, attach: Attach, sig: Signal) -> nix::Result<()> {
+    match attach {
+        Attach::Inherit => send_signal(pid, sig),
+        Attach::Prefixed => {
+            let pid = i32::try_from(pid).map_err(|_| nix::Error::EINVAL)?;
+            signal::killpg(Pid::from_raw(pid), sig)
+        }
+    }
+}
+
+fn send_signal(pid: u32, sig: Signal) -> nix::Result<()> {
+    let pid = i32::try_from(pid).map_err(|_| nix::Error::EINVAL)?;
+    signal::kill(Pid::from_raw(pid), sig)
+}
+
+/// Status to publish once a unit's process has exited. Anything that
+/// happens after we started shutting it down counts as a clean stop.
+fn final_status(status: ExitStatus, shutting_down: bool) -> (State, Option<String>) {
+    if shutting_down || status.success() {
+        return (State::Stopped, None);
+    }
+    let detail = match (status.code(), status.signal()) {
+        (Some(code), _) => format!("exit code {code}"),
+        (None, Some(sig)) => match Signal::try_from(sig) {
+            Ok(sig) => format!("killed by {}", sig.as_str()),
+            Err(_) => format!("killed by signal {sig}"),
+        },
+        (None, None) => "exited".to_string(),
+    };
+    (State::Failed, Some(detail))
+}
+
+/// Shell-style exit code for a finished child.
+fn exit_code(status: ExitStatus) -> i32 {
+    status
+        .code()
+        .or_else(|| status.signal().map(|sig| 128 + sig))
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn final_status_reports_exit_code() {
+        let (state, detail) = final_status(ExitStatus::from_raw(3 << 8), false);
+        assert_eq!(state, State::Failed);
+        assert_eq!(detail.as_deref(), Some("exit code 3"));
+    }
+
+    #[test]
+    fn final_status_is_stopped_on_success_or_shutdown() {
+        assert_eq!(
+            final_status(ExitStatus::from_raw(0), false).0,
+            State::Stopped
+        );
+        // SIGTERM (15) while we were shutting down is a clean stop.
+        assert_eq!(
+            final_status(ExitStatus::from_raw(15), true).0,
+            State::Stopped
+        );
+        let (state, detail) = final_status(ExitStatus::from_raw(9), false);
+        assert_eq!(state, State::Failed);
+        assert_eq!(detail.as_deref(), Some("killed by SIGKILL"));
+    }
+
+    #[test]
+    fn exit_code_maps_signals_like_a_shell() {
+        assert_eq!(exit_code(ExitStatus::from_raw(2 << 8)), 2);
+        assert_eq!(exit_code(ExitStatus::from_raw(15)), 143);
+    }
+
+    #[test]
+    fn run_publishes_and_cleans_up() {
+        let tmp = tempfile::tempdir().unwrap();
+        let meta = Meta::new(PathBuf::from("/code/app"), std::process::id());
+        let mut reg = Registration::attach(tmp.path(), "abc123", meta).unwrap();
+        let unit = UnitSpec::shell(
+            "job",
+            r#"test "$SUTRA_ENV_ID" = abc123 && test -f "$SUTRA_STATE_DIR/abc123.job.status""#,
+        );
+        assert_eq!(run(&mut reg, unit).unwrap(), 0);
+        let env = Environment::load(&tmp.path().join("abc123")).unwrap();
+        assert_eq!(env.units[0].state, State::Stopped);
+
+        drop(reg);
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn run_does_not_forward_sigint() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = |name: &str| tmp.path().join(name).display().to_string();
+        let (pid_file, count_file, stop_file) = (file("pid"), file("count"), file("stop"));
+        let mut unit = UnitSpec::shell(
+            "server",
+            r#"trap 'echo int >> "$COUNT"' INT; echo $$ > "$PID_FILE"; while [ ! -e "$STOP" ]; do sleep 0.05; done"#,
+        );
+        unit.env = vec![
+            ("PID_FILE".into(), pid_file.clone()),
+            ("COUNT".into(), count_file.clone()),
+            ("STOP".into(), stop_file.clone()),
+        ];
+        let state_dir = tmp.path().join("state");
+        let runner = thread::spawn(move || {
+            let meta = Meta::new(PathBuf::from("/code/app"), std::process::id());
+            let mut reg = Registration::attach(&state_dir, "abc123", meta).unwrap();
+            run(&mut reg, unit).unwrap()
+        });
+        let wait_for = |path: &str| {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while !std::fs::read_to_string(path).is_ok_and(|c| c.ends_with('\n')) {
+                assert!(Instant::now() < deadline, "timed out waiting for {path}");
+                thread::sleep(Duration::from_millis(10));
+            }
+        };
+        let ints = || {
+            std::fs::read_to_string(&count_file)
+                .unwrap()
+                .lines()
+                .count()
+        };
+        // Lets the child exit even if an assertion fails.
+        struct Stop(String);
+        impl Drop for Stop {
+            fn drop(&mut self) {
+                let _ = std::fs::write(&self.0, "");
+            }
+        }
+        let stop = Stop(stop_file);
+
+        // A terminal's Ctrl-C goes to the child and to us.
+        wait_for(&pid_file);
+        let child: u32 = std::fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        send_signal(child, Signal::SIGINT).unwrap();
+        wait_for(&count_file);
+        send_signal(std::process::id(), Signal::SIGINT).unwrap();
+        thread::sleep(Duration::from_millis(500));
+        let received = ints();
+
+        drop(stop);
+        assert_eq!(runner.join().unwrap(), 0);
+        assert_eq!(received, 1);
+        assert_eq!(ints(), 1);
+    }
+}
@@ -1,4 +1,5 @@
 use std::io;
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
 use crossterm::event::{
@@ -13,9 +14,12 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::Paragraph;
 
-use crate::model::{self, Environment, State};
+use crate::journal::Journal;
+use crate::model::{self, Environment, SinceTracker, State};
 use crate::notifications::Notifier;
+use crate::probe::{HealthProber, PortProber};
 use crate::watcher::RegistryWatcher;
+use crate::writer::{self, GcReport};
 
 /// Interval between automatic refreshes.
 const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
@@ -29,28 +33,54 @@ struct UnitRef {
 
 /// Application state for the TUI.
 struct App {
+    state_dir: PathBuf,
     envs: Vec<Environment>,
     scroll_offset: usize,
     notifier: Notifier,
+    /// Keeps each unit's "since" steady across status rewrites.
+    since: SinceTracker,
+    /// Checks whether each declared port is accepting connections.
+    ports: PortProber,
+    /// Runs the `<UNIT>_HEALTH` checks.
+    health: HealthProber,
     /// Index into the flattened list of all units across all environments.
     selected_unit: usize,
+    /// What `c` would remove, awaiting `y` to confirm.
+    pending_cleanup: Option<GcReport>,
+    /// Shown in the footer until the next key press.
+    message: Option<String>,
 }
 
 impl App {
-    fn new() -> Self {
-        let envs = model::load_all();
-        let mut notifier = Notifier::new();
+    fn new(state_dir: PathBuf) -> Self {
+        let mut envs = model::load_all(&state_dir);
+        let mut health = HealthProber::spawn();
+        health.apply(&mut envs);
+        let mut since = SinceTracker::new();
+        since.apply(&mut envs);
+        let mut ports = PortProber::spawn();
+        ports.apply(&mut envs);
+        let mut notifier = Notifier::new().with_journal(Journal::for_state_dir(&state_dir));
         notifier.process(&envs);
         App {
+            state_dir,
             envs,
             scroll_offset: 0,
             notifier,
+            since,
+            ports,
+            health,
             selected_unit: 0,
+            pending_cleanup: None,
+            message: None,
         }
     }
 
     fn refresh(&mut self) {
-        self.envs = model::load_all();
+        self.envs = model::load_all(&self.state_dir);
+        self.health.apply(&mut self.envs);
+        self.since.apply(&mut self.envs);
+        self.ports.apply(&mut self.envs);
         self.notifier.process(&self.envs);
     }
 
@@ -117,19 +147,42 @@ impl App {
         }
     }
 
-    /// Open browser for the selected unit's port.
-    fn open_selected_unit_browser(&self) {
+    /// Open the selected unit's `n`th link (0 = its default link).
+    fn open_selected_unit_link(&self, n: usize) {
         if let Some(r) = self.selected_unit_ref() {
             let env = &self.envs[r.env_index];
             let unit = &env.units[r.unit_index];
-            if let Some(port) = env.port_for(&unit.name) {
-                let _ = std::process::Command::new("open")
-                    .arg(format!("http://localhost:{port}"))
-                    .spawn();
+            if let Some(link) = env.links_for(&unit.name).get(n) {
+                crate::browser::open(&link.url);
+            }
+        }
+    }
+
+    /// List what cleaning up would remove and ask for confirmation.
+    fn preview_clean_up(&mut self) {
+        match writer::gc(&self.state_dir, writer::ORPHAN_MIN_AGE, true) {
+            Ok(report) if report.is_empty() => {
+                self.message = Some("Nothing to clean up".to_string());
             }
+            Ok(report) => {
+                self.message = Some(format!("Remove {}? y to confirm", report.summary()));
+                self.pending_cleanup = Some(report);
+            }
+            Err(e) => self.message = Some(format!("Clean up failed: {e}")),
         }
     }
 
+    /// Remove dead environments and orphaned status files, then reload.
+    fn clean_up_dead(&mut self) {
+        self.message = Some(
+            match writer::gc(&self.state_dir, writer::ORPHAN_MIN_AGE, false) {
+                Ok(report) => format!("Removed {}", report.summary()),
+                Err(e) => format!("Clean up failed: {e}"),
+            },
+        );
+        self.refresh();
+    }
+
     /// Send SIGTERM to the supervisor PID published in the env's meta
     /// file. Sutra is a situational-awareness dashboard with limited
     /// control — the supervisor owns its children and is responsible
@@ -138,7 +191,8 @@ impl App {
     fn terminate_selected_env(&self) {
         if let Some(r) = self.selected_unit_ref() {
             let env = &self.envs[r.env_index];
-            if env.alive {
+            // Re-check so a PID reused since the last refresh is never signaled.
+            if env.check_alive() {
                 if let Ok(raw_pid) = i32::try_from(env.pid) {
                     let _ = nix::sys::signal::kill(
                         nix::unistd::Pid::from_raw(raw_pid),
@@ -161,6 +215,23 @@ fn state_color(state: &State) -> Color {
     }
 }
 
+/// `✓` if the unit's port accepts connections, `✗` if not (red while the
+/// unit claims to be running or ready), blank until probed.
+fn listening_marker(listening: Option<bool>, state: &State) -> Span<'static> {
+    match listening {
+        Some(true) => Span::styled("\u{2713}", Style::default().fg(Color::Green)),
+        Some(false) => Span::styled(
+            "\u{2717}",
+            Style::default().fg(if matches!(state, State::Running | State::Ready) {
+                Color::Red
+            } else {
+                Color::DarkGray
+            }),
+        ),
+        None => Span::raw(" "),
+    }
+}
+
 /// Build the content lines for a single environment card.
 ///
 /// `selected_flat` is the globally selected flat unit index.
@@ -182,16 +253,29 @@ fn env_lines(
     // Alive indicator
     if env.alive {
         header_spans.push(Span::styled("● ", Style::default().fg(Color::Green)));
+    } else if env.stale_pid {
+        header_spans.push(Span::styled("○ ", Style::default().fg(Color::Yellow)));
     } else {
         header_spans.push(Span::styled("○ ", Style::default().fg(Color::DarkGray)));
     }
 
+    if let Some(icon) = &env.icon {
+        header_spans.push(Span::raw(format!("{icon} ")));
+    }
+
     // Project name — bold
     header_spans.push(Span::styled(
         name,
         Style::default().add_modifier(Modifier::BOLD),
     ));
 
+    if env.stale_pid {
+        header_spans.push(Span::styled(
+            format!("  stale PID {}", env.pid),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+
     // Elapsed — right side (we append as a dim span after a gap)
     header_spans.push(Span::styled(
         format!("  {elapsed}"),
@@ -200,7 +284,14 @@ fn env_lines(
 
     lines.push(Line::from(header_spans));
 
-    // Directory on a second line, dimmed
+    if let Some(description) = &env.description {
+        lines.push(Line::from(Span::styled(
+            format!("  {description}"),
+            Style::default().fg(Color::Gray),
+        )));
+    }
+
+    // Directory on its own line, dimmed
     let dir_str = env.dir.display().to_string();
     lines.push(Line::from(vec![Span::styled(
         format!("  {dir_str}"),
@@ -211,13 +302,21 @@ fn env_lines(
     let max_name_len = env.units.iter().map(|u| u.name.len()).max().unwrap_or(0);
 
     let has_any_port = env.units.iter().any(|u| env.port_for(&u.name).is_some());
-    // Ports are displayed as ":<port>" which is at most 6 chars (e.g. ":65535")
+    // Ports are displayed as ":<port>" which is at most 6 chars (e.g. ":65535"),
+    // followed by a one-char listening marker
     let port_col_w: usize = if has_any_port { 6 } else { 0 };
 
     let max_state_len = env
         .units
         .iter()
-        .map(|u| u.state.to_string().len())
+        .map(|u| u.observed_state().to_string().len())
+        .max()
+        .unwrap_or(0);
+    let since_col_w = env
+        .units
+        .iter()
+        .filter_map(|u| u.since_string())
+        .map(|s| s.len())
         .max()
         .unwrap_or(0);
 
@@ -228,8 +327,8 @@ fn env_lines(
         let is_muted = notifier.is_unit_muted(&env.id, &unit.name);
         let is_notif_off = notifier.is_unit_notifications_off(&env.id, &unit.name);
 
-        let indicator = unit.state.display_indicator();
-        let color = state_color(&unit.state);
+        let indicator = unit.style_state().display_indicator();
+        let color = state_color(unit.style_state());
 
         let name_color = if is_muted {
             Color::DarkGray
@@ -293,23 +392,75 @@ fn env_lines(
         // Port column (fixed width if any unit has a port)
         if has_any_port {
             if let Some(port) = env.port_for(&unit.name) {
+                let port_color = if env.port_conflict_for(&unit.name).is_some() {
+                    Color::Red
+                } else {
+                    Color::Cyan
+                };
                 spans.push(Span::styled(
                     format!("{:<width$}", format!(":{port}"), width = port_col_w),
-                    Style::default().fg(Color::Cyan),
+                    Style::default().fg(port_color),
                 ));
+                spans.push(listening_marker(env.listening_for(&unit.name), &unit.state));
             } else {
-                spans.push(Span::raw(format!("{:<width$}", "", width = port_col_w)));
+                spans.push(Span::raw(format!("{:<width$}", "", width = port_col_w + 1)));
             }
             spans.push(Span::raw("  "));
         }
 
         // State label (dynamically sized column)
-        let state_str = unit.state.to_string();
+        let state_str = unit.observed_state().to_string();
         spans.push(Span::styled(
             format!("{:<width$}", state_str, width = max_state_len),
             Style::default().fg(color),
         ));
 
+        // Time in this state (dynamically sized column)
+        if since_col_w > 0 {
+            let since = unit.since_string().unwrap_or_default();
+            spans.push(Span::styled(
+                format!("  {:>width$}", since, width = since_col_w),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+
+        // Latest health check result
+        if let Some(health) = env.health_for(&unit.name) {
+            spans.push(Span::styled(
+                format!("  {}", health.label()),
+                Style::default().fg(if health.ok() {
+                    Color::Green
+                } else {
+                    Color::Red
+                }),
+            ));
+        }
+
+        // Typical build/startup time, highlighted when the last run regressed
+        if let Some(summary) = notifier.stats().latest(&env.dir, &unit.name) {
+            let color = if summary.regression().is_some() {
+                Color::Yellow
+            } else {
+                Color::DarkGray
+            };
+            spans.push(Span::styled(
+                format!("  {}", summary.label()),
+                Style::default().fg(color),
+            ));
+        }
+
+        // Progress gauge for starting/building units
+        if let Some(progress) = unit.display_progress() {
+            spans.push(Span::styled(
+                format!("  {}", gauge(progress.fraction())),
+                Style::default().fg(color),
+            ));
+            spans.push(Span::styled(
+                format!(" {progress}"),
+                Style::default().fg(Color::Gray),
+            ));
+        }
+
         // Optional detail
         if let Some(ref detail) = unit.detail {
             spans.push(Span::styled(
@@ -318,12 +469,51 @@ fn env_lines(
             ));
         }
 
+        // Who else wants or has this unit's port
+        if let Some(conflict) = env.port_conflict_for(&unit.name) {
+            spans.push(Span::styled(
+                format!("  \u{26a0} {}", conflict.describe()),
+                Style::default().fg(Color::Red),
+            ));
+        }
+
+        // Links beyond the port, numbered for the 1-9 keys
+        let links = env.links_for(&unit.name);
+        let has_extra = links
+            .iter()
+            .any(|l| l.label.is_some() || env.port_for(&unit.name).is_none());
+        if has_extra {
+            let labels: Vec<String> = links
+                .iter()
+                .enumerate()
+                .map(|(i, l)| format!("{}:{}", i + 1, l.label.as_deref().unwrap_or("open")))
+                .collect();
+            spans.push(Span::styled(
+                format!("  \u{2197} {}", labels.join(" ")),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+
         lines.push(Line::from(spans));
     }
 
     lines
 }
 
+/// Width of the progress gauge, in cells.
+const GAUGE_WIDTH: usize = 10;
+
+/// Block gauge like `▕████░░░░░░▏` for a fraction in `0.0..=1.0`.
+fn gauge(fraction: f32) -> String {
+    let filled = (fraction * GAUGE_WIDTH as f32).round() as usize;
+    let filled = filled.min(GAUGE_WIDTH);
+    format!(
+        "\u{2595}{}{}\u{258f}",
+        "\u{2588}".repeat(filled),
+        "\u{2591}".repeat(GAUGE_WIDTH - filled)
+    )
+}
+
 /// Result of building content lines — includes line-to-unit mapping for mouse clicks.
 struct ContentLines {
     lines: Vec<Line<'static>>,
@@ -365,8 +555,9 @@ fn build_content_lines(
             line_to_unit.push(None);
         }
         let env_lines = env_lines(env, notifier, selected_unit, flat_offset);
-        // First 2 lines are header + dir path, rest are unit lines
-        let header_lines = 2usize.min(env_lines.len());
+        // Header lines (name, description, dir path) come first, then
+        // one line per unit
+        let header_lines = env_lines.len().saturating_sub(env.units.len());
         for _ in 0..header_lines {
             line_to_unit.push(None);
         }
@@ -383,8 +574,9 @@ fn build_content_lines(
     }
 }
 
-/// Entry point for the TUI. Called from main.
-pub fn run() {
+/// Entry point for the TUI. Called from main with the resolved registry
+/// directory.
+pub fn run(state_dir: PathBuf) {
     // Install a panic hook that restores the terminal before printing the panic.
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic_info| {
@@ -398,13 +590,13 @@ pub fn run() {
         original_hook(panic_info);
     }));
 
-    if let Err(e) = run_inner() {
+    if let Err(e) = run_inner(state_dir) {
         eprintln!("Error: {e}");
         std::process::exit(1);
     }
 }
 
-fn run_inner() -> io::Result<()> {
+fn run_inner(state_dir: PathBuf) -> io::Result<()> {
     // Setup terminal
     terminal::enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -421,11 +613,11 @@ fn run_inner() -> io::Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new();
-    let mut last_refresh = Instant::now();
-
     // Start file watcher (best-effort)
-    let watcher = RegistryWatcher::new().ok();
+    let watcher = RegistryWatcher::new(&state_dir).ok();
+
+    let mut app = App::new(state_dir);
+    let mut last_refresh = Instant::now();
 
     loop {
         // Clamp selection after any refresh
@@ -493,6 +685,13 @@ fn run_inner() -> io::Result<()> {
             // Footer
             let mut footer_spans: Vec<Span<'static>> = Vec::new();
 
+            if let Some(message) = &app.message {
+                footer_spans.push(Span::styled(
+                    format!("{message}    "),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+
             if app.notifier.global_mute {
                 footer_spans.push(Span::styled(
                     "\u{1f507} MUTED    ",
@@ -519,7 +718,7 @@ fn run_inner() -> io::Result<()> {
             };
             footer_spans.push(Span::styled(
                 format!(
-                    "q quit  r refresh  j/k select  {mute_label}  {notif_label}  M unit-mute  N unit-notif  o open  x stop"
+                    "q quit  r refresh  j/k select  {mute_label}  {notif_label}  M unit-mute  N unit-notif  o/1-9 open  x stop  c clean up"
                 ),
                 Style::default().fg(Color::DarkGray),
             ));
@@ -536,22 +735,38 @@ fn run_inner() -> io::Result<()> {
 
         if event::poll(timeout)? {
             match event::read()? {
-                Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
-                    KeyCode::Char('q') => break,
-                    KeyCode::Char('r') => {
-                        app.refresh();
-                        last_refresh = Instant::now();
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    app.message = None;
+                    // Any key but `y` cancels a pending clean-up.
+                    let pending_cleanup = app.pending_cleanup.take();
+                    match key.code {
+                        KeyCode::Char('y') if pending_cleanup.is_some() => {
+                            app.clean_up_dead();
+                            last_refresh = Instant::now();
+                        }
+                        _ if pending_cleanup.is_some() => {
+                            app.message = Some("Clean up cancelled".to_string());
+                        }
+                        KeyCode::Char('q') => break,
+                        KeyCode::Char('r') => {
+                            app.refresh();
+                            last_refresh = Instant::now();
+                        }
+                        KeyCode::Char('j') | KeyCode::Down => app.select_next(),
+                        KeyCode::Char('k') | KeyCode::Up => app.select_prev(),
+                        KeyCode::Char('m') => app.notifier.toggle_global_mute(),
+                        KeyCode::Char('n') => app.notifier.toggle_global_notifications(),
+                        KeyCode::Char('M') => app.toggle_selected_unit_mute(),
+                        KeyCode::Char('N') => app.toggle_selected_unit_notifications(),
+                        KeyCode::Char('o') => app.open_selected_unit_link(0),
+                        KeyCode::Char(c @ '1'..='9') => {
+                            app.open_selected_unit_link(c as usize - '1' as usize)
+                        }
+                        KeyCode::Char('x') => app.terminate_selected_env(),
+                        KeyCode::Char('c') => app.preview_clean_up(),
+                        _ => {}
                     }
-                    KeyCode::Char('j') | KeyCode::Down => app.select_next(),
-                    KeyCode::Char('k') | KeyCode::Up => app.select_prev(),
-                    KeyCode::Char('m') => app.notifier.toggle_global_mute(),
-                    KeyCode::Char('n') => app.notifier.toggle_global_notifications(),
-                    KeyCode::Char('M') => app.toggle_selected_unit_mute(),
-                    KeyCode::Char('N') => app.toggle_selected_unit_notifications(),
-                    KeyCode::Char('o') => app.open_selected_unit_browser(),
-                    KeyCode::Char('x') => app.terminate_selected_env(),
-                    _ => {}
-                },
+                }
                 Event::Mouse(mouse) => match mouse.kind {
                     MouseEventKind::Down(_) => {
                         // Map click row to a unit
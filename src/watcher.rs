@@ -3,7 +3,8 @@ use std::sync::mpsc;
 
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 
-use crate::model::state_dir;
+use crate::liveness::ExitMonitor;
+use crate::model::{Meta, is_meta_file};
 
 /// Events emitted by the registry watcher.
 #[derive(Debug, Clone)]
@@ -12,9 +13,16 @@ pub enum WatchEvent {
     EnvironmentChanged(String),
     /// An environment's meta file or status dotfile was removed.
     EnvironmentRemoved(String),
+    /// An environment's supervisor PID exited, whether or not it cleaned
+    /// up its files.
+    ProcessExited(String),
 }
 
-/// Watches ~/.dev-runner/ for filesystem changes and emits WatchEvents.
+/// Watches the registry directory for filesystem changes and emits WatchEvents.
+///
+/// Each registered environment's PID is also watched with an
+/// [`ExitMonitor`], so a supervisor that dies without cleaning up shows as
+/// dead right away instead of on the next periodic refresh.
 pub struct RegistryWatcher {
     _watcher: RecommendedWatcher,
     pub rx: mpsc::Receiver<WatchEvent>,
@@ -52,17 +60,40 @@ fn extract_hash_id(path: &Path) -> Option<String> {
     None
 }
 
-impl RegistryWatcher {
-    pub fn new() -> notify::Result<Self> {
-        let Some(dir) = state_dir() else {
-            return Err(notify::Error::generic("could not determine home directory"));
-        };
+/// Start watching the PID in the meta file at `path`, if it is one.
+fn watch_pid(monitor: &ExitMonitor, path: &Path) {
+    let Some(id) = path.file_name().and_then(|f| f.to_str()) else {
+        return;
+    };
+    if !is_meta_file(id) {
+        return;
+    }
+    if let Some(meta) = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|c| Meta::parse(&c))
+    {
+        monitor.watch(id, meta.pid);
+    }
+}
 
+impl RegistryWatcher {
+    /// Start watching `dir`, creating it first if it doesn't exist yet.
+    pub fn new(dir: &Path) -> notify::Result<Self> {
         // Ensure the directory exists
-        std::fs::create_dir_all(&dir).ok();
+        std::fs::create_dir_all(dir).ok();
 
         let (tx, rx) = mpsc::channel();
 
+        let exit_tx = tx.clone();
+        let monitor = ExitMonitor::spawn(move |id| {
+            let _ = exit_tx.send(WatchEvent::ProcessExited(id));
+        });
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                watch_pid(&monitor, &entry.path());
+            }
+        }
+
         let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
             let Ok(event) = res else { return };
 
@@ -71,9 +102,21 @@ impl RegistryWatcher {
                     continue;
                 };
 
+                let is_meta = path
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .is_some_and(is_meta_file);
                 let watch_event = match event.kind {
-                    EventKind::Remove(_) => WatchEvent::EnvironmentRemoved(id),
+                    EventKind::Remove(_) => {
+                        if is_meta {
+                            monitor.unwatch(&id);
+                        }
+                        WatchEvent::EnvironmentRemoved(id)
+                    }
                     EventKind::Create(_) | EventKind::Modify(_) => {
+                        if is_meta {
+                            watch_pid(&monitor, path);
+                        }
                         WatchEvent::EnvironmentChanged(id)
                     }
                     _ => continue,
@@ -84,7 +127,7 @@ impl RegistryWatcher {
             }
         })?;
 
-        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
 
         Ok(RegistryWatcher {
             _watcher: watcher,
@@ -0,0 +1,427 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::liveness::{self, Liveness};
+use crate::model::{Meta, State, UnitStatus, is_meta_file};
+
+/// A registered environment, owned by the process that publishes it.
+///
+/// Creating a handle writes the meta file; dropping it (or calling
+/// [`EnvironmentHandle::shutdown`]) removes the meta file and every status
+/// file belonging to the environment. Status content is rendered through
+/// [`UnitStatus`] and the meta file through [`Meta`], the same types the
+/// reader parses with.
+#[derive(Debug)]
+pub struct EnvironmentHandle {
+    state_dir: PathBuf,
+    id: String,
+    meta: Meta,
+    released: bool,
+}
+
+impl EnvironmentHandle {
+    /// Write `<state_dir>/<id>` and return a handle that owns it.
+    ///
+    /// Fails with `InvalidInput` if `id` isn't a valid meta filename — an
+    /// empty or dotted id would make cleanup match other environments'
+    /// files.
+    pub fn register(state_dir: &Path, id: &str, meta: Meta) -> io::Result<Self> {
+        write_meta(state_dir, id, &meta)?;
+        Ok(EnvironmentHandle {
+            state_dir: state_dir.to_path_buf(),
+            id: id.to_string(),
+            meta,
+            released: false,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn state_dir(&self) -> &Path {
+        &self.state_dir
+    }
+
+    pub fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    pub fn meta_path(&self) -> PathBuf {
+        self.state_dir.join(&self.id)
+    }
+
+    /// Atomically replace the status file for `unit`.
+    pub fn set_status(&self, unit: &str, state: State, detail: Option<&str>) -> io::Result<()> {
+        self.publish(&UnitStatus::new(unit, state, detail))
+    }
+
+    /// Atomically replace a unit's status file with `status`, including
+    /// its v2 fields (`PID`, `PROGRESS`, …).
+    pub fn publish(&self, status: &UnitStatus) -> io::Result<()> {
+        write_status(&self.state_dir, &self.id, status)
+    }
+
+    /// Remove the status file for `unit`, e.g. for a transient unit that
+    /// finished. Missing files are not an error.
+    pub fn clear_status(&self, unit: &str) -> io::Result<()> {
+        validate_unit_name(unit)?;
+        remove_if_exists(&status_path(&self.state_dir, &self.id, unit))
+    }
+
+    /// Remove the meta file and all status files now, reporting errors
+    /// that `Drop` would otherwise swallow.
+    pub fn shutdown(mut self) -> io::Result<()> {
+        self.released = true;
+        remove_environment(&self.state_dir, &self.id)
+    }
+}
+
+impl Drop for EnvironmentHandle {
+    fn drop(&mut self) {
+        if !self.released {
+            let _ = remove_environment(&self.state_dir, &self.id);
+        }
+    }
+}
+
+/// Path of the status file for `unit`, in the current (no leading dot)
+/// convention.
+pub fn status_path(state_dir: &Path, id: &str, unit: &str) -> PathBuf {
+    state_dir.join(format!("{}.{}.status", id, unit))
+}
+
+/// Atomically write the meta file `<state_dir>/<id>`, creating the
+/// directory if needed. Unlike [`EnvironmentHandle::register`], nothing
+/// removes it afterwards; the caller owns cleanup.
+pub fn write_meta(state_dir: &Path, id: &str, meta: &Meta) -> io::Result<()> {
+    validate_id(id)?;
+    fs::create_dir_all(state_dir)?;
+    write_atomic(&state_dir.join(id), &meta.to_string())
+}
+
+/// Atomically write one unit's status file for environment `id`.
+pub fn write_status(state_dir: &Path, id: &str, status: &UnitStatus) -> io::Result<()> {
+    validate_id(id)?;
+    validate_unit_name(&status.name)?;
+    write_atomic(
+        &status_path(state_dir, id, &status.name),
+        &format!("{}\n", status),
+    )
+}
+
+/// Remove the meta file for `id` and its status files in both naming
+/// conventions. Missing files are not an error.
+pub fn remove_environment(state_dir: &Path, id: &str) -> io::Result<()> {
+    validate_id(id)?;
+    remove_status_files(state_dir, id)?;
+    remove_if_exists(&state_dir.join(id))
+}
+
+/// Remove every status file for `id` — `<id>.*.status` and the legacy
+/// `.<id>.*.status` — and return how many were removed.
+pub fn remove_status_files(state_dir: &Path, id: &str) -> io::Result<usize> {
+    validate_id(id)?;
+    let new_prefix = format!("{}.", id);
+    let old_prefix = format!(".{}.", id);
+    let mut removed = 0;
+
+    let entries = match fs::read_dir(state_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    for entry in entries.flatten() {
+        let fname = entry.file_name();
+        let fname_str = fname.to_string_lossy();
+        let is_status = fname_str
+            .strip_prefix(&new_prefix)
+            .or_else(|| fname_str.strip_prefix(&old_prefix))
+            .and_then(|rest| rest.strip_suffix(".status"))
+            .is_some_and(|unit| !unit.is_empty());
+        if is_status {
+            remove_if_exists(&entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Status files whose meta file is missing are left alone until they are
+/// at least this old, whatever `min_age` [`gc`] is given: a writer may
+/// publish a unit a moment before its meta file lands.
+pub const ORPHAN_MIN_AGE: Duration = Duration::from_secs(5);
+
+/// What [`gc`] removed, or would remove on a dry run.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GcReport {
+    /// ID and `DIR` of each dead environment. Its meta file and status
+    /// files go with it.
+    pub environments: Vec<(String, PathBuf)>,
+    /// Status files whose environment has no meta file.
+    pub orphans: Vec<PathBuf>,
+}
+
+impl GcReport {
+    pub fn is_empty(&self) -> bool {
+        self.environments.is_empty() && self.orphans.is_empty()
+    }
+
+    /// What the report covers, e.g. "2 dead environments and 1 orphaned
+    /// status file".
+    pub fn summary(&self) -> String {
+        let count = |n: usize, what: &str| match n {
+            1 => format!("1 {what}"),
+            n => format!("{n} {what}s"),
+        };
+        match (self.environments.len(), self.orphans.len()) {
+            (0, 0) => "nothing".to_string(),
+            (envs, 0) => count(envs, "dead environment"),
+            (0, orphans) => count(orphans, "orphaned status file"),
+            (envs, orphans) => format!(
+                "{} and {}",
+                count(envs, "dead environment"),
+                count(orphans, "orphaned status file")
+            ),
+        }
+    }
+}
+
+/// Remove environments whose PID is dead (or reused, see
+/// [`liveness::check`]), along with their status files
+/// in both naming conventions, and status files whose meta file is gone.
+///
+/// Only files untouched for at least `min_age` are considered; for a dead
+/// environment that means its meta file and every status file. Orphans
+/// must also be [`ORPHAN_MIN_AGE`] old. Meta files that don't parse are
+/// left alone. With `dry_run`, nothing is deleted.
+pub fn gc(state_dir: &Path, min_age: Duration, dry_run: bool) -> io::Result<GcReport> {
+    let entries = match fs::read_dir(state_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(GcReport::default()),
+        Err(e) => return Err(e),
+    };
+    let now = SystemTime::now();
+    let old_enough = |mtime: SystemTime, min_age: Duration| {
+        now.duration_since(mtime).is_ok_and(|age| age >= min_age)
+    };
+
+    let mut metas = Vec::new();
+    let mut statuses: HashMap<String, Vec<(PathBuf, SystemTime)>> = HashMap::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let Ok(mtime) = entry.metadata().and_then(|m| m.modified()) else {
+            continue;
+        };
+        if is_meta_file(&name) {
+            metas.push((name.into_owned(), entry.path(), mtime));
+        } else if let Some(id) = status_file_id(&name) {
+            statuses
+                .entry(id.to_string())
+                .or_default()
+                .push((entry.path(), mtime));
+        }
+    }
+
+    let mut report = GcReport::default();
+    let mut doomed = Vec::new();
+    for (id, path, mtime) in metas {
+        let files = statuses.remove(&id).unwrap_or_default();
+        let Some(meta) = fs::read_to_string(&path).ok().and_then(|c| Meta::parse(&c)) else {
+            continue;
+        };
+        let newest = files.iter().map(|f| f.1).fold(mtime, SystemTime::max);
+        if liveness::check(meta.pid, meta.started) == Liveness::Alive
+            || !old_enough(newest, min_age)
+        {
+            continue;
+        }
+        report.environments.push((id, meta.dir));
+        doomed.extend(files.into_iter().map(|f| f.0));
+        doomed.push(path);
+    }
+    for (_, files) in statuses {
+        for (path, mtime) in files {
+            if old_enough(mtime, min_age.max(ORPHAN_MIN_AGE)) {
+                report.orphans.push(path.clone());
+                doomed.push(path);
+            }
+        }
+    }
+    report.environments.sort();
+    report.orphans.sort();
+
+    if !dry_run {
+        for path in doomed {
+            remove_if_exists(&path)?;
+        }
+    }
+    Ok(report)
+}
+
+/// The environment ID of a status filename in either convention
+/// (`<id>.<unit>.status` or `.<id>.<unit>.status`).
+fn status_file_id(name: &str) -> Option<&str> {
+    let rest = name.strip_prefix('.').unwrap_or(name);
+    let (id, unit) = rest.strip_suffix(".status")?.split_once('.')?;
+    (is_meta_file(id) && !unit.is_empty()).then_some(id)
+}
+
+/// Unit names become part of the status filename, so they must be
+/// non-empty and free of `.` (the field separator) and path separators.
+pub fn validate_unit_name(unit: &str) -> io::Result<()> {
+    if unit.is_empty() || unit.contains(['.', '/', '\\']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid unit name {unit:?}: must be non-empty and contain no '.' or '/'"),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_id(id: &str) -> io::Result<()> {
+    if !is_meta_file(id) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid environment id {id:?}: must be a non-empty hex string"),
+        ));
+    }
+    Ok(())
+}
+
+/// Write-then-rename so readers never observe a truncated file. The temp
+/// name ends in `.tmp`, which neither the loader nor the watcher treat as
+/// a meta or status file.
+fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", std::process::id()));
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Environment, load_all};
+
+    fn meta(dir: &str) -> Meta {
+        let mut meta = Meta::new(PathBuf::from(dir), std::process::id());
+        meta.started = Some(1700000000);
+        meta.ports.insert("server".into(), 3000);
+        meta
+    }
+
+    #[test]
+    fn register_and_set_status_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let handle = EnvironmentHandle::register(tmp.path(), "abc123", meta("/code/app")).unwrap();
+        handle
+            .set_status("server", State::Building, Some("cargo"))
+            .unwrap();
+
+        let env = Environment::load(&handle.meta_path()).unwrap();
+        assert_eq!(env.dir, PathBuf::from("/code/app"));
+        assert_eq!(env.port_for("server"), Some(3000));
+        assert_eq!(env.units.len(), 1);
+        assert_eq!(env.units[0].state, State::Building);
+        assert_eq!(env.units[0].detail.as_deref(), Some("cargo"));
+    }
+
+    #[test]
+    fn drop_removes_meta_and_both_status_conventions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let other = tmp.path().join("fff000.server.status");
+        fs::write(&other, "ready\n").unwrap();
+        {
+            let handle =
+                EnvironmentHandle::register(tmp.path(), "abc123", meta("/code/app")).unwrap();
+            handle.set_status("server", State::Ready, None).unwrap();
+            fs::write(tmp.path().join(".abc123.vite.status"), "ready\n").unwrap();
+        }
+        assert!(load_all(tmp.path()).is_empty());
+        let remaining: Vec<_> = fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(remaining, vec![other.file_name().unwrap().to_owned()]);
+    }
+
+    #[test]
+    fn gc_removes_dead_environments_and_orphans() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut alive = meta("/code/alive");
+        alive.started = Some(crate::model::now_secs());
+        write_meta(dir, "a1", &alive).unwrap();
+        fs::write(dir.join("a1.server.status"), "ready\n").unwrap();
+        let mut dead = meta("/code/dead");
+        dead.pid = u32::MAX;
+        write_meta(dir, "d1", &dead).unwrap();
+        fs::write(dir.join("d1.server.status"), "ready\n").unwrap();
+        fs::write(dir.join(".d1.vite.status"), "ready\n").unwrap();
+        fs::write(dir.join("0f.server.status"), "failed\n").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(dir.join("0f.server.status"))
+            .unwrap()
+            .set_modified(SystemTime::now() - ORPHAN_MIN_AGE)
+            .unwrap();
+        // Too fresh: its meta file may be about to be written.
+        fs::write(dir.join("0e.server.status"), "starting\n").unwrap();
+        fs::write(dir.join("notes.txt"), "keep\n").unwrap();
+
+        assert!(
+            gc(dir, Duration::from_secs(3600), false)
+                .unwrap()
+                .is_empty()
+        );
+        let report = gc(dir, Duration::ZERO, true).unwrap();
+        assert_eq!(
+            report.environments,
+            [("d1".to_string(), PathBuf::from("/code/dead"))]
+        );
+        assert_eq!(report.orphans, [dir.join("0f.server.status")]);
+        assert_eq!(
+            report.summary(),
+            "1 dead environment and 1 orphaned status file"
+        );
+        assert!(dir.join("d1").exists());
+
+        assert_eq!(gc(dir, Duration::ZERO, false).unwrap(), report);
+        let mut remaining: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            ["0e.server.status", "a1", "a1.server.status", "notes.txt"]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_ids_and_unit_names() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(EnvironmentHandle::register(tmp.path(), "", meta("/a")).is_err());
+        assert!(remove_environment(tmp.path(), "not-hex").is_err());
+
+        let handle = EnvironmentHandle::register(tmp.path(), "abc123", meta("/a")).unwrap();
+        assert!(handle.set_status("a.b", State::Ready, None).is_err());
+        assert!(handle.set_status("", State::Ready, None).is_err());
+        assert!(handle.set_status("../x", State::Ready, None).is_err());
+    }
+}